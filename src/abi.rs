@@ -0,0 +1,473 @@
+// solc-compatible ABI JSON, built from the parsed declarations instead of a compiler run
+use serde::Serialize;
+
+use crate::ast::{
+    ContractDefinition, ContractKind, DataLocation, Error, Event, Function, FunctionType,
+    Mutability, Parameter, SpecialFunction, SpecialKind, Type, Variable, Visibility,
+};
+
+// keys are declared in the alphabetical order solc writes them in
+#[derive(Serialize, Debug, PartialEq)]
+pub struct AbiParameter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<AbiParameter>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed: Option<bool>,
+    #[serde(rename = "internalType")]
+    pub internal_type: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct AbiItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anonymous: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<Vec<AbiParameter>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<Vec<AbiParameter>>,
+    #[serde(rename = "stateMutability", skip_serializing_if = "Option::is_none")]
+    pub state_mutability: Option<&'static str>,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct ContractAbi {
+    pub contract: String,
+    pub file: String,
+    pub abi: Vec<AbiItem>,
+}
+
+impl ContractAbi {
+    // expects a flattened contract, the ABI includes everything inherited
+    pub fn from_contract(contract: &ContractDefinition, file: &str) -> Self {
+        let library = contract.kind == ContractKind::Library;
+        let mut abi = Vec::new();
+
+        abi.extend(contract.special_functions.iter().map(special_item));
+        abi.extend(
+            contract
+                .functions
+                .iter()
+                .filter(|f| matches!(f.visibility, Visibility::External | Visibility::Public))
+                .map(|f| function_item(f, library)),
+        );
+        abi.extend(
+            contract
+                .variables
+                .iter()
+                .filter(|v| v.visibility == Visibility::Public)
+                .map(getter_item),
+        );
+        abi.extend(contract.events.iter().map(event_item));
+        abi.extend(contract.errors.iter().map(error_item));
+
+        // solc orders entries by type, then name
+        abi.sort_by(|a, b| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+
+        ContractAbi {
+            contract: contract.name.clone(),
+            file: file.to_string(),
+            abi,
+        }
+    }
+}
+
+fn mutability(mutability: &Option<Mutability>) -> &'static str {
+    match mutability {
+        Some(Mutability::Pure) => "pure",
+        Some(Mutability::View) => "view",
+        Some(Mutability::Payable) => "payable",
+        None => "nonpayable",
+    }
+}
+
+fn function_item(func: &Function, library: bool) -> AbiItem {
+    AbiItem {
+        anonymous: None,
+        inputs: Some(
+            func.parameters
+                .iter()
+                .map(|p| parameter(p, library))
+                .collect(),
+        ),
+        name: Some(func.name.clone()),
+        outputs: Some(
+            func.returns
+                .iter()
+                .flatten()
+                .map(|p| parameter(p, library))
+                .collect(),
+        ),
+        state_mutability: Some(mutability(&func.mutability)),
+        kind: "function",
+    }
+}
+
+// public state variables are read through a view function of the same name
+fn getter_item(var: &Variable) -> AbiItem {
+    AbiItem {
+        anonymous: None,
+        inputs: Some(
+            var.getter_inputs()
+                .iter()
+                .map(|p| parameter(p, false))
+                .collect(),
+        ),
+        name: Some(var.name.clone()),
+        outputs: Some(
+            var.getter_returns()
+                .iter()
+                .map(|p| parameter(p, false))
+                .collect(),
+        ),
+        state_mutability: Some("view"),
+        kind: "function",
+    }
+}
+
+fn special_item(special: &SpecialFunction) -> AbiItem {
+    let (kind, inputs) = match special.kind {
+        SpecialKind::Constructor => (
+            "constructor",
+            Some(
+                special
+                    .parameters
+                    .iter()
+                    .map(|p| parameter(p, false))
+                    .collect(),
+            ),
+        ),
+        SpecialKind::Fallback => ("fallback", None),
+        SpecialKind::Receive => ("receive", None),
+    };
+
+    AbiItem {
+        anonymous: None,
+        inputs,
+        name: None,
+        outputs: None,
+        state_mutability: Some(mutability(&special.mutability)),
+        kind,
+    }
+}
+
+fn event_item(event: &Event) -> AbiItem {
+    AbiItem {
+        anonymous: Some(event.anonymous),
+        inputs: Some(
+            event
+                .parameters
+                .iter()
+                .map(|p| AbiParameter {
+                    indexed: Some(p.indexed),
+                    ..abi_parameter(p.name.as_deref(), &p.type_name)
+                })
+                .collect(),
+        ),
+        name: Some(event.name.clone()),
+        outputs: None,
+        state_mutability: None,
+        kind: "event",
+    }
+}
+
+fn error_item(error: &Error) -> AbiItem {
+    AbiItem {
+        anonymous: None,
+        inputs: Some(
+            error
+                .parameters
+                .iter()
+                .map(|p| parameter(p, false))
+                .collect(),
+        ),
+        name: Some(error.name.clone()),
+        outputs: None,
+        state_mutability: None,
+        kind: "error",
+    }
+}
+
+// storage references of library functions are passed as slots and named as such
+fn parameter(param: &Parameter, library: bool) -> AbiParameter {
+    let abi = abi_parameter(param.name.as_deref(), &param.type_name);
+    if library && param.data_location == Some(DataLocation::Storage) {
+        return AbiParameter {
+            components: None,
+            internal_type: format!("{} storage", abi.internal_type),
+            type_name: format!("{} storage", param.type_name.library_name()),
+            ..abi
+        };
+    }
+    abi
+}
+
+fn abi_parameter(name: Option<&str>, t: &Type) -> AbiParameter {
+    let (type_name, internal_type, components) = abi_type(t);
+    AbiParameter {
+        components,
+        indexed: None,
+        internal_type,
+        name: name.unwrap_or_default().to_string(),
+        type_name,
+    }
+}
+
+// the ABI type, the source type solc reports as internalType, and the members of tuples
+fn abi_type(t: &Type) -> (String, String, Option<Vec<AbiParameter>>) {
+    match t {
+        Type::Array(inner) => {
+            let (type_name, internal, components) = abi_type(inner);
+            (
+                format!("{}[]", type_name),
+                format!("{}[]", internal),
+                components,
+            )
+        }
+        Type::FixedArray(inner, size) => {
+            let (type_name, internal, components) = abi_type(inner);
+            (
+                format!("{}[{}]", type_name, size),
+                format!("{}[{}]", internal, size),
+                components,
+            )
+        }
+        Type::Struct(name, members) => (
+            "tuple".to_string(),
+            format!("struct {}", name),
+            Some(
+                members
+                    .iter()
+                    .map(|m| abi_parameter(m.name.as_deref(), &m.type_name))
+                    .collect(),
+            ),
+        ),
+        Type::Enum(name) => (t.canonical(), format!("enum {}", name), None),
+        Type::Contract(name) => (t.canonical(), format!("contract {}", name), None),
+        Type::UserValue(name, _) => (t.canonical(), name.clone(), None),
+        Type::Function(func) => (t.canonical(), function_type_name(func), None),
+        Type::Elementary(name) if name == "address payable" => (t.canonical(), name.clone(), None),
+        Type::Elementary(_) | Type::Mapping(_, _) | Type::UserDefined(_) => {
+            (t.canonical(), t.canonical(), None)
+        }
+    }
+}
+
+// function (uint256) external view returns (bool)
+fn function_type_name(func: &FunctionType) -> String {
+    let types = |params: &[Parameter]| {
+        params
+            .iter()
+            .map(|p| abi_type(&p.type_name).1)
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+
+    let mut name = format!("function ({})", types(&func.parameters));
+    if func.visibility == Visibility::External {
+        name.push_str(" external");
+    }
+    if func.mutability.is_some() {
+        name.push(' ');
+        name.push_str(mutability(&func.mutability));
+    }
+    if !func.returns.is_empty() {
+        name.push_str(&format!(" returns ({})", types(&func.returns)));
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn abi(input: &str) -> serde_json::Value {
+        let mut lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer.tokenize());
+        let unit = parser.parse_all_symbols();
+        let contract = unit.contracts.last().unwrap();
+        serde_json::to_value(ContractAbi::from_contract(contract, "test.sol").abi).unwrap()
+    }
+
+    #[test]
+    fn functions_and_getters() {
+        let abi = abi(r#"
+            interface IERC20 {}
+            contract Vault {
+                struct Position { address owner; uint128 size; IERC20[] tokens; }
+                enum Side { Buy, Sell }
+                mapping(address => Position) public positions;
+                constructor(IERC20 token) payable {}
+                receive() external payable {}
+                function open(Position calldata p, Side side) external returns (uint256 id) {}
+                function fee() public pure returns (uint16) {}
+                function _internal() internal {}
+            }
+        "#);
+
+        assert_eq!(
+            abi,
+            serde_json::json!([
+                {
+                    "inputs": [
+                        { "internalType": "contract IERC20", "name": "token", "type": "address" }
+                    ],
+                    "stateMutability": "payable",
+                    "type": "constructor"
+                },
+                {
+                    "inputs": [],
+                    "name": "fee",
+                    "outputs": [{ "internalType": "uint16", "name": "", "type": "uint16" }],
+                    "stateMutability": "pure",
+                    "type": "function"
+                },
+                {
+                    "inputs": [
+                        {
+                            "components": [
+                                { "internalType": "address", "name": "owner", "type": "address" },
+                                { "internalType": "uint128", "name": "size", "type": "uint128" },
+                                {
+                                    "internalType": "contract IERC20[]",
+                                    "name": "tokens",
+                                    "type": "address[]"
+                                }
+                            ],
+                            "internalType": "struct Vault.Position",
+                            "name": "p",
+                            "type": "tuple"
+                        },
+                        { "internalType": "enum Vault.Side", "name": "side", "type": "uint8" }
+                    ],
+                    "name": "open",
+                    "outputs": [{ "internalType": "uint256", "name": "id", "type": "uint256" }],
+                    "stateMutability": "nonpayable",
+                    "type": "function"
+                },
+                {
+                    "inputs": [{ "internalType": "address", "name": "", "type": "address" }],
+                    "name": "positions",
+                    "outputs": [
+                        { "internalType": "address", "name": "owner", "type": "address" },
+                        { "internalType": "uint128", "name": "size", "type": "uint128" }
+                    ],
+                    "stateMutability": "view",
+                    "type": "function"
+                },
+                { "stateMutability": "payable", "type": "receive" }
+            ])
+        );
+    }
+
+    #[test]
+    fn events_and_errors() {
+        let abi = abi(r#"
+            contract Token {
+                event Transfer(address indexed from, address indexed to, uint256 value);
+                event Raw(bytes32) anonymous;
+                error Insufficient(uint256 available);
+            }
+        "#);
+
+        assert_eq!(
+            abi,
+            serde_json::json!([
+                {
+                    "inputs": [
+                        { "internalType": "uint256", "name": "available", "type": "uint256" }
+                    ],
+                    "name": "Insufficient",
+                    "type": "error"
+                },
+                {
+                    "anonymous": true,
+                    "inputs": [
+                        { "indexed": false, "internalType": "bytes32", "name": "", "type": "bytes32" }
+                    ],
+                    "name": "Raw",
+                    "type": "event"
+                },
+                {
+                    "anonymous": false,
+                    "inputs": [
+                        { "indexed": true, "internalType": "address", "name": "from", "type": "address" },
+                        { "indexed": true, "internalType": "address", "name": "to", "type": "address" },
+                        { "indexed": false, "internalType": "uint256", "name": "value", "type": "uint256" }
+                    ],
+                    "name": "Transfer",
+                    "type": "event"
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn address_payable() {
+        let abi = abi(r#"
+            contract Treasury {
+                address payable public owner;
+                function pay(address payable[] calldata to) external {}
+            }
+        "#);
+
+        assert_eq!(
+            abi[1]["inputs"][0],
+            serde_json::json!({
+                "internalType": "address payable[]",
+                "name": "to",
+                "type": "address[]"
+            })
+        );
+        assert_eq!(
+            abi[0]["outputs"][0],
+            serde_json::json!({ "internalType": "address payable", "name": "", "type": "address" })
+        );
+    }
+
+    #[test]
+    fn value_types() {
+        let abi = abi(r#"
+            type Price is uint128;
+            contract Market {
+                type Qty is uint64;
+                function order(Price p, Qty q) external {}
+            }
+        "#);
+
+        assert_eq!(
+            abi[0]["inputs"],
+            serde_json::json!([
+                { "internalType": "Price", "name": "p", "type": "uint128" },
+                { "internalType": "Market.Qty", "name": "q", "type": "uint64" }
+            ])
+        );
+    }
+
+    #[test]
+    fn library_storage_parameters() {
+        let abi = abi(r#"
+            library Positions {
+                struct Position { uint256 size; }
+                function grow(Position storage p, uint256 by) external {}
+            }
+        "#);
+
+        assert_eq!(
+            abi[0]["inputs"][0],
+            serde_json::json!({
+                "internalType": "struct Positions.Position storage",
+                "name": "p",
+                "type": "Positions.Position storage"
+            })
+        );
+    }
+}
@@ -0,0 +1,325 @@
+// compiler output from Foundry (out/Token.sol/Token.json) and Hardhat
+// (artifacts/contracts/Token.sol/Token.json); the ABI in them is the ground truth the
+// selectors derived from source can be checked against
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::output::{FunctionOutput, Kind};
+use crate::selector::{compute_selector, compute_topic, format_selector, format_topic};
+
+#[derive(Deserialize)]
+struct ArtifactFile {
+    abi: Option<Vec<AbiEntry>>,
+    #[serde(rename = "contractName")]
+    contract_name: Option<String>, // hardhat only
+    #[serde(rename = "sourceName")]
+    source_name: Option<String>, // hardhat only, contracts/Token.sol
+    #[serde(rename = "methodIdentifiers", default)]
+    method_identifiers: BTreeMap<String, String>, // foundry only, signature => selector
+}
+
+#[derive(Deserialize)]
+struct AbiEntry {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    inputs: Vec<AbiInput>,
+    #[serde(default)]
+    anonymous: bool,
+}
+
+#[derive(Deserialize)]
+struct AbiInput {
+    #[serde(rename = "type")]
+    type_name: String,
+    #[serde(default)]
+    components: Vec<AbiInput>,
+}
+
+impl AbiInput {
+    // tuple[2] -> (uint256,address)[2]
+    fn canonical(&self) -> String {
+        match self.type_name.strip_prefix("tuple") {
+            Some(suffix) => format!("({}){}", signature_types(&self.components), suffix),
+            None => self.type_name.clone(),
+        }
+    }
+}
+
+fn signature_types(inputs: &[AbiInput]) -> String {
+    inputs
+        .iter()
+        .map(AbiInput::canonical)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub struct Artifact {
+    pub contract: String,
+    pub source: Option<PathBuf>, // the file the contract was compiled from, as far as known
+    pub path: PathBuf,
+    pub rows: Vec<FunctionOutput>,
+}
+
+impl Artifact {
+    // None for json files that are no artifacts, like build info and hardhat's .dbg.json
+    pub fn load(path: &Path) -> std::io::Result<Option<Self>> {
+        let text = std::fs::read_to_string(path)?;
+        let file: ArtifactFile = serde_json::from_str(&text)?;
+        Ok(Self::from_file(file, path))
+    }
+
+    fn from_file(file: ArtifactFile, path: &Path) -> Option<Self> {
+        let abi = file.abi?;
+
+        // foundry names the file after the contract, with the compiler version when
+        // several were used: Token.json, Token.0.8.19.json
+        let contract = file.contract_name.unwrap_or_else(|| {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            name.split('.').next().unwrap_or_default().to_string()
+        });
+        // foundry writes out/Token.sol/Token.json, naming the directory after the file
+        let source = file.source_name.map(PathBuf::from).or_else(|| {
+            path.parent()
+                .and_then(|dir| dir.file_name())
+                .and_then(|n| n.to_str())
+                .filter(|n| n.ends_with(".sol") || n.ends_with(".vy"))
+                .map(PathBuf::from)
+        });
+        let location = path.display().to_string();
+        let row = |kind, selector, signature| FunctionOutput {
+            selector,
+            kind,
+            signature,
+            contract: Some(contract.clone()),
+            file: location.clone(),
+            line: None,
+            visibility: "external".to_string(),
+        };
+
+        let mut rows = Vec::new();
+        // library functions taking structs or storage references have selectors that
+        // cannot be derived from their ABI, take the compiler's when it lists them
+        if file.method_identifiers.is_empty() {
+            rows.extend(abi.iter().filter(|e| e.kind == "function").map(|e| {
+                let signature = format!("{}({})", e.name, signature_types(&e.inputs));
+                row(
+                    Kind::Function,
+                    format_selector(&compute_selector(&signature)),
+                    signature,
+                )
+            }));
+        } else {
+            rows.extend(file.method_identifiers.iter().map(|(signature, selector)| {
+                row(Kind::Function, format!("0x{}", selector), signature.clone())
+            }));
+        }
+        for entry in &abi {
+            let signature = format!("{}({})", entry.name, signature_types(&entry.inputs));
+            match entry.kind.as_str() {
+                "event" if !entry.anonymous => rows.push(row(
+                    Kind::Event,
+                    format_topic(&compute_topic(&signature)),
+                    signature,
+                )),
+                "error" => rows.push(row(
+                    Kind::Error,
+                    format_selector(&compute_selector(&signature)),
+                    signature,
+                )),
+                _ => {}
+            }
+        }
+
+        Some(Artifact {
+            contract,
+            source,
+            path: path.to_path_buf(),
+            rows,
+        })
+    }
+
+    // whether `path` may be the source file the contract was compiled from
+    pub fn is_from(&self, path: &Path) -> bool {
+        self.source
+            .as_ref()
+            .is_none_or(|source| path.ends_with(source))
+    }
+
+    // what the artifact and the contract parsed from source disagree on. solc also lists
+    // events and errors declared elsewhere that a contract uses, so those only count as
+    // missing when they are found nowhere in the sources (`known`)
+    pub fn compare<'a>(
+        &'a self,
+        source: &'a [FunctionOutput],
+        known: &HashSet<(Kind, &str)>,
+    ) -> Differences<'a> {
+        let key = |row: &'a FunctionOutput| (row.kind, row.selector.as_str());
+        let in_source: HashSet<_> = source.iter().map(key).collect();
+        let in_artifact: HashSet<_> = self.rows.iter().map(key).collect();
+
+        Differences {
+            missing: self
+                .rows
+                .iter()
+                .filter(|r| match r.kind {
+                    Kind::Function => !in_source.contains(&key(r)),
+                    Kind::Event | Kind::Error => !known.contains(&key(r)),
+                })
+                .collect(),
+            unexpected: source
+                .iter()
+                .filter(|r| !in_artifact.contains(&key(r)))
+                .collect(),
+        }
+    }
+}
+
+pub struct Differences<'a> {
+    pub missing: Vec<&'a FunctionOutput>, // compiled, but not found in the sources
+    pub unexpected: Vec<&'a FunctionOutput>, // in the sources, but not compiled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str, path: &str) -> Option<Artifact> {
+        Artifact::from_file(serde_json::from_str(json).unwrap(), Path::new(path))
+    }
+
+    #[test]
+    fn hardhat() {
+        let json = r#"{
+            "_format": "hh-sol-artifact-1",
+            "contractName": "Router",
+            "sourceName": "contracts/Router.sol",
+            "abi": [
+                {
+                    "type": "function",
+                    "name": "swap",
+                    "inputs": [
+                        {
+                            "name": "route",
+                            "type": "tuple[]",
+                            "components": [
+                                { "name": "pool", "type": "address" },
+                                { "name": "fee", "type": "uint24" }
+                            ]
+                        },
+                        { "name": "amount", "type": "uint256" }
+                    ],
+                    "outputs": [],
+                    "stateMutability": "nonpayable"
+                },
+                {
+                    "type": "event",
+                    "name": "Transfer",
+                    "anonymous": false,
+                    "inputs": [
+                        { "indexed": true, "name": "from", "type": "address" },
+                        { "indexed": true, "name": "to", "type": "address" },
+                        { "indexed": false, "name": "value", "type": "uint256" }
+                    ]
+                },
+                { "type": "event", "name": "Raw", "anonymous": true, "inputs": [] },
+                { "type": "error", "name": "Expired", "inputs": [] },
+                { "type": "receive", "stateMutability": "payable" }
+            ],
+            "bytecode": "0x"
+        }"#;
+
+        let artifact = parse(json, "artifacts/contracts/Router.sol/Router.json").unwrap();
+        assert_eq!(artifact.contract, "Router");
+        assert!(artifact.is_from(Path::new("./contracts/Router.sol")));
+        assert!(!artifact.is_from(Path::new("./test/Router.sol")));
+
+        let rows: Vec<(&str, &str)> = artifact
+            .rows
+            .iter()
+            .map(|r| (r.kind.as_str(), r.signature.as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("function", "swap((address,uint24)[],uint256)"),
+                ("event", "Transfer(address,address,uint256)"),
+                ("error", "Expired()"),
+            ]
+        );
+        assert_eq!(
+            artifact.rows[1].selector,
+            "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        );
+
+        // build info and debug files have no abi
+        assert!(
+            parse(
+                r#"{ "buildInfo": "../build-info/x.json" }"#,
+                "Router.dbg.json"
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn foundry_and_compare() {
+        let json = r#"{
+            "abi": [
+                { "type": "function", "name": "transfer", "inputs": [], "outputs": [] },
+                { "type": "error", "name": "Unauthorized", "inputs": [] }
+            ],
+            "methodIdentifiers": {
+                "grow(Positions.Position storage,uint256)": "9f8d4c1a",
+                "transfer(address,uint256)": "a9059cbb"
+            }
+        }"#;
+        let artifact = parse(json, "out/Token.sol/Token.0.8.19.json").unwrap();
+        assert_eq!(artifact.contract, "Token");
+        assert!(artifact.is_from(Path::new("src/Token.sol")));
+        assert!(!artifact.is_from(Path::new("src/mocks/TokenMock.sol")));
+        assert_eq!(artifact.rows[0].selector, "0x9f8d4c1a");
+        assert_eq!(artifact.rows[1].signature, "transfer(address,uint256)");
+
+        let source = vec![
+            FunctionOutput {
+                selector: "0xa9059cbb".to_string(),
+                kind: Kind::Function,
+                signature: "transfer(address,uint256)".to_string(),
+                contract: Some("Token".to_string()),
+                file: "src/Token.sol".to_string(),
+                line: Some(4),
+                visibility: "external".to_string(),
+            },
+            FunctionOutput {
+                selector: "0x40c10f19".to_string(),
+                kind: Kind::Function,
+                signature: "mint(address,uint256)".to_string(),
+                contract: Some("Token".to_string()),
+                file: "src/Token.sol".to_string(),
+                line: Some(8),
+                visibility: "external".to_string(),
+            },
+        ];
+
+        // the error is declared in a file the contract imports
+        let unauthorized = format_selector(&compute_selector("Unauthorized()"));
+        let known = HashSet::from([(Kind::Error, unauthorized.as_str())]);
+
+        let differences = artifact.compare(&source, &known);
+        let missing: Vec<&str> = differences
+            .missing
+            .iter()
+            .map(|r| r.signature.as_str())
+            .collect();
+        assert_eq!(missing, ["grow(Positions.Position storage,uint256)"]);
+        assert_eq!(differences.unexpected[0].signature, "mint(address,uint256)");
+    }
+}
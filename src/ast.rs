@@ -1,34 +1,119 @@
+use crate::span::Span;
+use crate::version::VersionReq;
+
+// everything parsed from one file; file-level functions are free functions
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SourceUnit {
+    pub solidity: Option<VersionReq>, // pragma solidity
+    pub imports: Vec<Import>,
+    pub contracts: Vec<ContractDefinition>,
+    pub functions: Vec<Function>,
+    pub events: Vec<Event>,
+    pub errors: Vec<Error>,
+}
+
+// import "./A.sol";                  path only
+// import "./A.sol" as A;             alias, same for import * as A from "./A.sol"
+// import {B, C as D} from "./A.sol"; symbols with their optional alias
+#[derive(Clone, Debug, PartialEq)]
+pub struct Import {
+    pub path: String,
+    pub alias: Option<String>,
+    pub symbols: Vec<(String, Option<String>)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContractDefinition {
+    pub kind: ContractKind,
+    pub name: String,
+    pub bases: Vec<String>,
+    pub functions: Vec<Function>,
+    pub variables: Vec<Variable>,
+    pub events: Vec<Event>,
+    pub errors: Vec<Error>,
+    pub special_functions: Vec<SpecialFunction>,
+    pub span: Span,
+}
+
+impl ContractDefinition {
+    pub fn special_function(&self, kind: SpecialKind) -> Option<&SpecialFunction> {
+        self.special_functions.iter().find(|f| f.kind == kind)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContractKind {
+    Contract,
+    Abstract,
+    Interface,
+    Library,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Variable {
     pub type_name: Type,
     pub name: String,
     pub visibility: Visibility,
-    pub returns: Parameter,
+    pub overrides: Option<Vec<String>>, // override(A, B); empty for a bare override
+    pub span: Span,
 }
 
 impl Variable {
     pub fn signature(&self) -> String {
-        let params = self.getter_params(&self.type_name);
-        if params.is_empty() {
-            format!("{}()", self.name)
-        } else {
-            format!("{}({})", self.name, params.join(","))
-        }
+        let params: Vec<String> = self
+            .getter_inputs()
+            .iter()
+            .map(|p| p.type_name.canonical())
+            .collect();
+        format!("{}({})", self.name, params.join(","))
     }
 
-    fn getter_params(&self, t: &Type) -> Vec<String> {
-        match t {
-            Type::Array(_) | Type::FixedArray(_, _) => {
-                vec!["uint256".to_string()]
-            }
-            Type::Mapping(key, value) => {
-                let mut params = vec![key.canonical()];
-                if let Type::Mapping(_, _) = value.as_ref() {
-                    params.extend(self.getter_params(value));
+    // every mapping level takes its key and every array level an index, however they nest
+    pub fn getter_inputs(&self) -> Vec<Parameter> {
+        let mut inputs = Vec::new();
+        let mut value = &self.type_name;
+        loop {
+            let (input, inner) = match value {
+                Type::Array(inner) | Type::FixedArray(inner, _) => {
+                    (Type::Elementary("uint256".to_string()), inner)
                 }
-                params
-            }
-            Type::Elementary(_) => vec![],
+                Type::Mapping(key, inner) => ((**key).clone(), inner),
+                _ => return inputs,
+            };
+            inputs.push(Parameter {
+                type_name: input,
+                name: None,
+                data_location: None,
+            });
+            value = inner;
+        }
+    }
+
+    // what the getter returns once every mapping and array level has been indexed.
+    // structs are returned member by member, without their mapping and array members
+    pub fn getter_returns(&self) -> Vec<Parameter> {
+        let mut value = &self.type_name;
+        while let Type::Array(inner) | Type::FixedArray(inner, _) | Type::Mapping(_, inner) = value
+        {
+            value = inner;
+        }
+
+        match value {
+            Type::Struct(_, members) => members
+                .iter()
+                .filter(|m| {
+                    !matches!(
+                        m.type_name,
+                        Type::Mapping(_, _) | Type::Array(_) | Type::FixedArray(_, _)
+                    )
+                })
+                .cloned()
+                .collect(),
+            _ => vec![Parameter {
+                type_name: value.clone(),
+                name: None,
+                data_location: None,
+            }],
         }
     }
 }
@@ -39,7 +124,11 @@ pub struct Function {
     pub parameters: Vec<Parameter>,
     pub visibility: Visibility,
     pub mutability: Option<Mutability>,
+    pub is_virtual: bool,
+    pub overrides: Option<Vec<String>>, // override(A, B); empty for a bare override
+    pub modifiers: Vec<String>,         // onlyOwner, onlyRole(ADMIN) recorded as onlyRole
     pub returns: Option<Vec<Parameter>>,
+    pub span: Span,
 }
 
 impl Function {
@@ -52,6 +141,102 @@ impl Function {
 
         format!("{}({})", self.name, param_types.join(","))
     }
+
+    // external library functions are called with structs and enums by name and may take
+    // storage references, so solc derives their selectors from a different signature
+    pub fn library_signature(&self) -> String {
+        let param_types: Vec<String> = self
+            .parameters
+            .iter()
+            .map(|p| match p.data_location {
+                Some(DataLocation::Storage) => format!("{} storage", p.type_name.library_name()),
+                _ => p.type_name.library_name(),
+            })
+            .collect();
+
+        format!("{}({})", self.name, param_types.join(","))
+    }
+}
+
+// functions reached without a selector: on deployment, or when no selector matches
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpecialFunction {
+    pub kind: SpecialKind,
+    pub parameters: Vec<Parameter>,
+    pub mutability: Option<Mutability>,
+    pub span: Span,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpecialKind {
+    Constructor, // also `function Token()` named after its contract, before 0.5.0
+    Fallback,    // also the unnamed `function() payable` before 0.6.0
+    Receive,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    pub name: String,
+    pub parameters: Vec<EventParameter>,
+    pub anonymous: bool,
+    pub span: Span,
+}
+
+impl Event {
+    pub fn signature(&self) -> String {
+        let param_types: Vec<String> = self
+            .parameters
+            .iter()
+            .map(|p| p.type_name.canonical())
+            .collect();
+
+        format!("{}({})", self.name, param_types.join(","))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventParameter {
+    pub type_name: Type,
+    pub name: Option<String>,
+    pub indexed: bool,
+}
+
+// error InsufficientBalance(uint256 available, uint256 required);
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub span: Span,
+}
+
+impl Error {
+    pub fn signature(&self) -> String {
+        let param_types: Vec<String> = self
+            .parameters
+            .iter()
+            .map(|p| p.type_name.canonical())
+            .collect();
+
+        format!("{}({})", self.name, param_types.join(","))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Struct {
+    pub name: String,
+    pub members: Vec<Parameter>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Enum {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserValueType {
+    pub name: String,
+    pub underlying: Type,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -63,10 +248,25 @@ pub struct Parameter {
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Type {
-    Elementary(String),            // uint256, address, bool
-    Array(Box<Type>),              // uint256[]
-    FixedArray(Box<Type>, usize),  // uint256[3]
-    Mapping(Box<Type>, Box<Type>), // mapping(address => uint256)
+    Elementary(String),             // uint256, address, bool
+    Array(Box<Type>),               // uint256[]
+    FixedArray(Box<Type>, usize),   // uint256[3]
+    Mapping(Box<Type>, Box<Type>),  // mapping(address => uint256)
+    UserDefined(String),            // Order, Lib.Order (unresolved)
+    Struct(String, Vec<Parameter>), // Order resolved to its members
+    Enum(String),                   // enum Side { Buy, Sell }
+    Contract(String),               // IERC20, any contract or interface
+    UserValue(String, Box<Type>),   // type Price is uint128
+    Function(FunctionType),         // function (uint256) external returns (bool)
+}
+
+// the type of a function pointer; external ones are passed in the ABI as address + selector
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionType {
+    pub parameters: Vec<Parameter>,
+    pub visibility: Visibility, // internal unless declared external
+    pub mutability: Option<Mutability>,
+    pub returns: Vec<Parameter>,
 }
 
 impl Type {
@@ -76,6 +276,9 @@ impl Type {
                 "uint" => "uint256".to_string(),
                 "int" => "int256".to_string(),
                 "byte" => "bytes1".to_string(),
+                "fixed" => "fixed128x18".to_string(),
+                "ufixed" => "ufixed128x18".to_string(),
+                "address payable" => "address".to_string(),
                 _ => name.clone(),
             },
             Type::Array(inner) => format!("{}[]", inner.canonical()),
@@ -83,6 +286,66 @@ impl Type {
             Type::Mapping(key, value) => {
                 format!("mapping({} => {})", key.canonical(), value.canonical())
             }
+            Type::UserDefined(name) => name.clone(),
+            Type::Struct(_, members) => {
+                let types: Vec<String> = members.iter().map(|m| m.type_name.canonical()).collect();
+                format!("({})", types.join(","))
+            }
+            Type::Enum(_) => "uint8".to_string(),
+            Type::Contract(_) => "address".to_string(),
+            Type::UserValue(_, underlying) => underlying.canonical(),
+            Type::Function(_) => "function".to_string(),
+        }
+    }
+
+    // the type as it appears in a library function signature: structs and enums by their
+    // fully qualified name, everything else as in the contract ABI
+    pub fn library_name(&self) -> String {
+        match self {
+            Type::Array(inner) => format!("{}[]", inner.library_name()),
+            Type::FixedArray(inner, size) => format!("{}[{}]", inner.library_name(), size),
+            Type::Mapping(key, value) => {
+                format!(
+                    "mapping({} => {})",
+                    key.library_name(),
+                    value.library_name()
+                )
+            }
+            Type::Struct(name, _) | Type::Enum(name) => name.clone(),
+            _ => self.canonical(),
+        }
+    }
+}
+
+// sizes solc accepts: uintN and intN for N in 8..=256 in steps of 8, bytesN for N in 1..=32,
+// fixedMxN and ufixedMxN with M like uintN and N at most 80
+pub fn is_valid_elementary(name: &str) -> bool {
+    let bits = |m: &str| {
+        m.parse::<u32>()
+            .is_ok_and(|m| (8..=256).contains(&m) && m % 8 == 0)
+    };
+
+    match name {
+        "address" | "bool" | "string" | "byte" | "bytes" | "uint" | "int" | "fixed" | "ufixed" => {
+            true
+        }
+        _ => {
+            if let Some(size) = name.strip_prefix("bytes") {
+                size.parse::<u32>().is_ok_and(|n| (1..=32).contains(&n))
+            } else if let Some(size) = name
+                .strip_prefix("ufixed")
+                .or_else(|| name.strip_prefix("fixed"))
+            {
+                size.split_once('x')
+                    .is_some_and(|(m, n)| bits(m) && n.parse::<u32>().is_ok_and(|n| n <= 80))
+            } else if let Some(size) = name
+                .strip_prefix("uint")
+                .or_else(|| name.strip_prefix("int"))
+            {
+                bits(size)
+            } else {
+                false
+            }
         }
     }
 }
@@ -0,0 +1,286 @@
+// local selector database; every (selector, signature) pair is stored once, with where
+// and when it was first seen
+use std::path::Path;
+
+use rusqlite::{Connection, params};
+use serde::Serialize;
+
+use crate::output::{FunctionOutput, Kind};
+
+const KINDS: [Kind; 3] = [Kind::Function, Kind::Event, Kind::Error];
+
+pub struct Database {
+    conn: Connection,
+}
+
+// a stored row; `first_seen` is UTC, as `YYYY-MM-DD HH:MM:SS`
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Entry {
+    pub selector: String,
+    pub kind: Kind,
+    pub signature: String,
+    pub contract: Option<String>,
+    pub file: String,
+    pub line: Option<usize>,
+    pub first_seen: String,
+}
+
+impl Entry {
+    pub fn location(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}:{}", self.file, line),
+            None => self.file.clone(),
+        }
+    }
+}
+
+// how many rows were new and how many were already known
+#[derive(Debug, Default, PartialEq)]
+pub struct Added {
+    pub new: usize,
+    pub known: usize,
+}
+
+// functions, events and errors live in tables of their own
+fn table(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Function => "functions",
+        Kind::Event => "events",
+        Kind::Error => "errors",
+    }
+}
+
+impl Database {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> rusqlite::Result<Self> {
+        for kind in KINDS {
+            conn.execute_batch(&format!(
+                "CREATE TABLE IF NOT EXISTS {table} (
+                    id INTEGER PRIMARY KEY,
+                    selector TEXT NOT NULL,
+                    signature TEXT NOT NULL,
+                    contract TEXT,
+                    file TEXT NOT NULL,
+                    line INTEGER,
+                    first_seen TEXT NOT NULL DEFAULT (datetime('now')),
+                    UNIQUE (selector, signature)
+                );
+                CREATE INDEX IF NOT EXISTS {table}_signature ON {table} (signature);",
+                table = table(kind)
+            ))?;
+        }
+
+        Ok(Database { conn })
+    }
+
+    // rows already known keep the provenance they were first added with
+    pub fn add(&mut self, rows: &[FunctionOutput]) -> rusqlite::Result<Added> {
+        let tx = self.conn.transaction()?;
+        let mut added = Added::default();
+
+        for row in rows {
+            let inserted = tx.execute(
+                &format!(
+                    "INSERT INTO {} (selector, signature, contract, file, line)
+                     VALUES (?1, ?2, ?3, ?4, ?5)
+                     ON CONFLICT (selector, signature) DO NOTHING",
+                    table(row.kind)
+                ),
+                params![
+                    row.selector,
+                    row.signature,
+                    row.contract,
+                    row.file,
+                    row.line
+                ],
+            )?;
+            if inserted > 0 {
+                added.new += 1;
+            } else {
+                added.known += 1;
+            }
+        }
+
+        tx.commit()?;
+        Ok(added)
+    }
+
+    // entries whose selector starts with `term`, in any case, or whose signature contains
+    // it; all entries without a term
+    pub fn query(&self, term: Option<&str>, kind: Option<Kind>) -> rusqlite::Result<Vec<Entry>> {
+        let term = term.unwrap_or("");
+        let mut entries = Vec::new();
+
+        for kind in KINDS
+            .into_iter()
+            .filter(|k| kind.is_none_or(|kind| kind == *k))
+        {
+            entries.extend(self.select(
+                kind,
+                // no LIKE, `_` and `%` in the term are matched as they are
+                "lower(substr(selector, 1, length(?1))) = lower(?1) OR instr(signature, ?1) > 0",
+                term,
+            )?);
+        }
+
+        Ok(entries)
+    }
+
+    // every signature known for a normalized selector: 4-byte selectors are functions or
+    // errors, 32-byte topics events
+    pub fn lookup(&self, selector: &str) -> rusqlite::Result<Vec<Entry>> {
+        let kinds: &[Kind] = if selector.len() == 66 {
+            &[Kind::Event]
+        } else {
+            &[Kind::Function, Kind::Error]
+        };
+
+        let mut entries = Vec::new();
+        for kind in kinds {
+            entries.extend(self.select(*kind, "selector = ?1", selector)?);
+        }
+        Ok(entries)
+    }
+
+    fn select(&self, kind: Kind, condition: &str, param: &str) -> rusqlite::Result<Vec<Entry>> {
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT selector, signature, contract, file, line, first_seen FROM {}
+             WHERE {}
+             ORDER BY signature, selector",
+            table(kind),
+            condition
+        ))?;
+        let rows = statement.query_map([param], |row| {
+            Ok(Entry {
+                selector: row.get(0)?,
+                kind,
+                signature: row.get(1)?,
+                contract: row.get(2)?,
+                file: row.get(3)?,
+                line: row.get(4)?,
+                first_seen: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
+
+impl From<Entry> for FunctionOutput {
+    fn from(entry: Entry) -> Self {
+        FunctionOutput {
+            selector: entry.selector,
+            kind: entry.kind,
+            signature: entry.signature,
+            contract: entry.contract,
+            file: entry.file,
+            line: entry.line,
+            visibility: "public".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(kind: Kind, selector: &str, signature: &str, contract: &str) -> FunctionOutput {
+        FunctionOutput {
+            selector: selector.to_string(),
+            kind,
+            signature: signature.to_string(),
+            contract: Some(contract.to_string()),
+            file: format!("src/{}.sol", contract),
+            line: Some(3),
+            visibility: "external".to_string(),
+        }
+    }
+
+    #[test]
+    fn add_and_query() {
+        let mut db = Database::open_in_memory().unwrap();
+        let transfer = row(
+            Kind::Function,
+            "0xa9059cbb",
+            "transfer(address,uint256)",
+            "Token",
+        );
+        let event = row(
+            Kind::Event,
+            "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+            "Transfer(address,address,uint256)",
+            "Token",
+        );
+
+        let added = db.add(&[transfer, event]).unwrap();
+        assert_eq!(added, Added { new: 2, known: 0 });
+
+        // the first contract seen keeps the entry
+        let again = row(
+            Kind::Function,
+            "0xa9059cbb",
+            "transfer(address,uint256)",
+            "Other",
+        );
+        let added = db.add(&[again]).unwrap();
+        assert_eq!(added, Added { new: 0, known: 1 });
+
+        let entries = db.query(Some("0xA9059C"), None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].contract.as_deref(), Some("Token"));
+        assert_eq!(entries[0].location(), "src/Token.sol:3");
+
+        // case matters in signatures: transfer and Transfer are different things
+        let entries = db.query(Some("Transfer("), None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kind, Kind::Event);
+
+        // no wildcards in terms
+        db.add(&[row(Kind::Function, "0x1e23c0e5", "setA(uint256)", "Token")])
+            .unwrap();
+        assert_eq!(db.query(Some("set_"), None).unwrap(), vec![]);
+        assert_eq!(db.query(Some("0x%"), None).unwrap(), vec![]);
+
+        assert_eq!(db.query(None, Some(Kind::Error)).unwrap(), vec![]);
+        assert_eq!(db.query(None, None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn lookup() {
+        let mut db = Database::open_in_memory().unwrap();
+        db.add(&[
+            row(Kind::Function, "0x42966c68", "burn(uint256)", "Token"),
+            row(
+                Kind::Function,
+                "0x42966c68",
+                "collate_propagate_storage(bytes16)",
+                "Fake",
+            ),
+            row(Kind::Error, "0x42966c68", "Unrelated()", "Token"),
+            row(Kind::Event, "0x42966c68aa", "Burn(uint256)", "Token"),
+        ])
+        .unwrap();
+
+        let signatures: Vec<String> = db
+            .lookup("0x42966c68")
+            .unwrap()
+            .into_iter()
+            .map(|e| e.signature)
+            .collect();
+        assert_eq!(
+            signatures,
+            [
+                "burn(uint256)",
+                "collate_propagate_storage(bytes16)",
+                "Unrelated()"
+            ]
+        );
+        assert_eq!(db.lookup("0x42966c").unwrap(), vec![]);
+    }
+}
@@ -0,0 +1,404 @@
+// EVM disassembler and the dispatcher patterns compilers emit, to recover the selectors of
+// deployed contracts without their source
+
+pub struct Instruction<'a> {
+    pub offset: usize,
+    pub opcode: u8,
+    pub immediate: &'a [u8], // the pushed bytes, cut short when the code ends first
+}
+
+impl Instruction<'_> {
+    pub fn mnemonic(&self) -> String {
+        match mnemonic(self.opcode) {
+            Some(name) => name.to_string(),
+            None => format!("UNKNOWN(0x{:02x})", self.opcode),
+        }
+    }
+
+    pub fn is_push(&self) -> bool {
+        (0x60..=0x7f).contains(&self.opcode)
+    }
+
+    fn value(&self) -> u64 {
+        self.immediate
+            .iter()
+            .take(8)
+            .fold(0, |value, byte| (value << 8) | u64::from(*byte))
+    }
+}
+
+// `0x6080...`, with or without the prefix or quotes, as saved from eth_getCode
+pub fn decode_hex(text: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    let text = text.trim().trim_matches('"');
+    let digits: String = text
+        .strip_prefix("0x")
+        .unwrap_or(text)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    hex::decode(digits)
+}
+
+pub fn disassemble(code: &[u8]) -> Vec<Instruction<'_>> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+
+    while offset < code.len() {
+        let opcode = code[offset];
+        let size = if (0x60..=0x7f).contains(&opcode) {
+            usize::from(opcode - 0x5f)
+        } else {
+            0
+        };
+        let end = (offset + 1 + size).min(code.len());
+        instructions.push(Instruction {
+            offset,
+            opcode,
+            immediate: &code[offset + 1..end],
+        });
+        offset = end;
+    }
+
+    instructions
+}
+
+// a selector compared against the one in calldata, and the code it dispatches to
+#[derive(Debug, PartialEq)]
+pub struct DispatchEntry {
+    pub selector: [u8; 4],
+    pub offset: usize, // of the push of the selector
+    pub entry: usize,  // where execution continues when the selector matches
+}
+
+const EQ: u8 = 0x14;
+const ISZERO: u8 = 0x15;
+const XOR: u8 = 0x18;
+const SUB: u8 = 0x03;
+const JUMPI: u8 = 0x57;
+
+// every `PUSHn selector ... EQ ... PUSH dest JUMPI` in the code. this covers solc's linear
+// dispatch and the branches of its binary search, where only the leaves compare for
+// equality and the GT pivots are skipped, as well as vyper's per-bucket checks, which jump
+// away on `XOR` when the selector does not match
+pub fn find_selectors(code: &[u8]) -> Vec<DispatchEntry> {
+    let instructions = disassemble(code);
+    let mut entries: Vec<DispatchEntry> = Vec::new();
+
+    // selectors with leading zero bytes are pushed with fewer bytes, down to PUSH0 for
+    // 0x00000000. such small constants are compared in function bodies as well, so they only
+    // count between loading the calldata selector and the first function dispatched to
+    let mut dispatcher = dispatcher_start(&instructions).map(|start| start..usize::MAX);
+
+    // the end of the last comparison found, pushes before it belong to that one
+    let mut matched_until = 0;
+    for (i, push) in instructions.iter().enumerate() {
+        if !(0x5f..=0x63).contains(&push.opcode)
+            || push.immediate.len() != usize::from(push.opcode - 0x5f)
+            || push.offset < matched_until
+        {
+            continue;
+        }
+        let in_dispatcher = dispatcher
+            .as_ref()
+            .is_some_and(|range| range.contains(&push.offset));
+        if push.opcode < 0x62 && !in_dispatcher {
+            continue;
+        }
+        let Some((entry, end)) = dispatch_target(&instructions[i + 1..]) else {
+            continue;
+        };
+        matched_until = end;
+        if in_dispatcher && let Some(range) = dispatcher.as_mut() {
+            range.end = range.end.min(entry);
+        }
+
+        let selector = (push.value() as u32).to_be_bytes();
+        if !entries.iter().any(|e| e.selector == selector) {
+            entries.push(DispatchEntry {
+                selector,
+                offset: push.offset,
+                entry,
+            });
+        }
+    }
+
+    entries.sort_by_key(|e| e.selector);
+    entries
+}
+
+// the offset after `PUSH1 0 CALLDATALOAD PUSH1 0xe0 SHR`, which leaves the selector of the
+// call on the stack for the dispatcher to compare against
+fn dispatcher_start(instructions: &[Instruction]) -> Option<usize> {
+    instructions.windows(4).find_map(|w| {
+        let zero = matches!(w[0].opcode, 0x5f | 0x60) && w[0].value() == 0;
+        let preamble = zero
+            && w[1].opcode == 0x35
+            && w[2].opcode == 0x60
+            && w[2].value() == 0xe0
+            && w[3].opcode == 0x1c;
+        preamble.then(|| w[3].offset + 1)
+    })
+}
+
+// where a comparison following a selector push continues on a match, if it is one, and
+// the offset of its JUMPI: DUP/SWAP or loading the calldata selector, the comparison, an
+// optional ISZERO, then the push of the jump destination and JUMPI
+fn dispatch_target(rest: &[Instruction]) -> Option<(usize, usize)> {
+    let mut rest = rest.iter().peekable();
+
+    let mut loads = 0;
+    while rest.next_if(|i| is_selector_load(i)).is_some() {
+        loads += 1;
+        if loads > 3 {
+            return None;
+        }
+    }
+
+    let compare = rest.next()?.opcode;
+    if !matches!(compare, EQ | XOR | SUB) {
+        return None;
+    }
+    let negated = rest.next_if(|i| i.opcode == ISZERO).is_some();
+    let dest = rest
+        .next()
+        .filter(|i| i.is_push() && i.immediate.len() <= 4)?;
+    let jumpi = rest.next().filter(|i| i.opcode == JUMPI)?;
+
+    // EQ jumps to the function, XOR and SUB jump away unless negated
+    let jumps_on_match = (compare == EQ) != negated;
+    let entry = if jumps_on_match {
+        dest.value() as usize
+    } else {
+        jumpi.offset + 1
+    };
+    Some((entry, jumpi.offset))
+}
+
+// what may sit between the selector push and the comparison: stack shuffling, or reading
+// the selector from calldata or memory as older vyper versions do
+fn is_selector_load(i: &Instruction) -> bool {
+    matches!(
+        i.opcode,
+        0x80..=0x9f // DUPn, SWAPn
+            | 0x35 // CALLDATALOAD
+            | 0x51 // MLOAD
+            | 0x1c // SHR
+            | 0x5f // PUSH0
+            | 0x60 // PUSH1
+    )
+}
+
+pub fn mnemonic(opcode: u8) -> Option<&'static str> {
+    const PUSH: [&str; 32] = [
+        "PUSH1", "PUSH2", "PUSH3", "PUSH4", "PUSH5", "PUSH6", "PUSH7", "PUSH8", "PUSH9", "PUSH10",
+        "PUSH11", "PUSH12", "PUSH13", "PUSH14", "PUSH15", "PUSH16", "PUSH17", "PUSH18", "PUSH19",
+        "PUSH20", "PUSH21", "PUSH22", "PUSH23", "PUSH24", "PUSH25", "PUSH26", "PUSH27", "PUSH28",
+        "PUSH29", "PUSH30", "PUSH31", "PUSH32",
+    ];
+    const DUP: [&str; 16] = [
+        "DUP1", "DUP2", "DUP3", "DUP4", "DUP5", "DUP6", "DUP7", "DUP8", "DUP9", "DUP10", "DUP11",
+        "DUP12", "DUP13", "DUP14", "DUP15", "DUP16",
+    ];
+    const SWAP: [&str; 16] = [
+        "SWAP1", "SWAP2", "SWAP3", "SWAP4", "SWAP5", "SWAP6", "SWAP7", "SWAP8", "SWAP9", "SWAP10",
+        "SWAP11", "SWAP12", "SWAP13", "SWAP14", "SWAP15", "SWAP16",
+    ];
+    const LOG: [&str; 5] = ["LOG0", "LOG1", "LOG2", "LOG3", "LOG4"];
+
+    let name = match opcode {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x03 => "SUB",
+        0x04 => "DIV",
+        0x05 => "SDIV",
+        0x06 => "MOD",
+        0x07 => "SMOD",
+        0x08 => "ADDMOD",
+        0x09 => "MULMOD",
+        0x0a => "EXP",
+        0x0b => "SIGNEXTEND",
+        0x10 => "LT",
+        0x11 => "GT",
+        0x12 => "SLT",
+        0x13 => "SGT",
+        0x14 => "EQ",
+        0x15 => "ISZERO",
+        0x16 => "AND",
+        0x17 => "OR",
+        0x18 => "XOR",
+        0x19 => "NOT",
+        0x1a => "BYTE",
+        0x1b => "SHL",
+        0x1c => "SHR",
+        0x1d => "SAR",
+        0x20 => "KECCAK256",
+        0x30 => "ADDRESS",
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x33 => "CALLER",
+        0x34 => "CALLVALUE",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x37 => "CALLDATACOPY",
+        0x38 => "CODESIZE",
+        0x39 => "CODECOPY",
+        0x3a => "GASPRICE",
+        0x3b => "EXTCODESIZE",
+        0x3c => "EXTCODECOPY",
+        0x3d => "RETURNDATASIZE",
+        0x3e => "RETURNDATACOPY",
+        0x3f => "EXTCODEHASH",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "PREVRANDAO",
+        0x45 => "GASLIMIT",
+        0x46 => "CHAINID",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x49 => "BLOBHASH",
+        0x4a => "BLOBBASEFEE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x53 => "MSTORE8",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x56 => "JUMP",
+        0x57 => "JUMPI",
+        0x58 => "PC",
+        0x59 => "MSIZE",
+        0x5a => "GAS",
+        0x5b => "JUMPDEST",
+        0x5c => "TLOAD",
+        0x5d => "TSTORE",
+        0x5e => "MCOPY",
+        0x5f => "PUSH0",
+        0x60..=0x7f => PUSH[usize::from(opcode - 0x60)],
+        0x80..=0x8f => DUP[usize::from(opcode - 0x80)],
+        0x90..=0x9f => SWAP[usize::from(opcode - 0x90)],
+        0xa0..=0xa4 => LOG[usize::from(opcode - 0xa0)],
+        0xf0 => "CREATE",
+        0xf1 => "CALL",
+        0xf2 => "CALLCODE",
+        0xf3 => "RETURN",
+        0xf4 => "DELEGATECALL",
+        0xf5 => "CREATE2",
+        0xfa => "STATICCALL",
+        0xfd => "REVERT",
+        0xfe => "INVALID",
+        0xff => "SELFDESTRUCT",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selectors(code: &str) -> Vec<(String, usize)> {
+        find_selectors(&decode_hex(code).unwrap())
+            .iter()
+            .map(|e| (hex::encode(e.selector), e.entry))
+            .collect()
+    }
+
+    #[test]
+    fn disassembly() {
+        let code = decode_hex("0x6080604052 600436 10 61").unwrap();
+        let listing: Vec<String> = disassemble(&code)
+            .iter()
+            .map(|i| format!("{} {} {}", i.offset, i.mnemonic(), hex::encode(i.immediate)))
+            .collect();
+        assert_eq!(
+            listing,
+            [
+                "0 PUSH1 80",
+                "2 PUSH1 40",
+                "4 MSTORE ",
+                "5 PUSH1 04",
+                "7 CALLDATASIZE ",
+                "8 LT ",
+                "9 PUSH2 "
+            ]
+        );
+    }
+
+    #[test]
+    fn solc_linear() {
+        // CALLDATALOAD(0) >> 224, then DUP1 PUSH4 sel EQ PUSH2 dest JUMPI for each function
+        let code = "5f35 60e0 1c \
+                    80 63a9059cbb 14 61003e 57 \
+                    80 6270a082 14 610050 57 \
+                    5f 80 fd";
+        assert_eq!(
+            selectors(code),
+            [
+                ("0070a082".to_string(), 0x50),
+                ("a9059cbb".to_string(), 0x3e)
+            ]
+        );
+    }
+
+    #[test]
+    fn short_selectors() {
+        // two leading zero bytes make a PUSH2, an all-zero selector a PUSH0
+        let code = "5f35 60e0 1c \
+                    80 610001 14 610040 57 \
+                    80 5f 14 610050 57 \
+                    80 6002 14 610060 57";
+        assert_eq!(
+            selectors(code),
+            [
+                ("00000000".to_string(), 0x50),
+                ("00000001".to_string(), 0x40),
+                ("00000002".to_string(), 0x60)
+            ]
+        );
+    }
+
+    #[test]
+    fn solc_binary_search() {
+        // the GT pivot is not a comparison for equality
+        let code = "80 6370a08231 11 610040 57 \
+                    80 6318160ddd 14 610050 57 \
+                    80 6370a08231 14 610060 57 \
+                    5b 80 63a9059cbb 14 610070 57";
+        let found: Vec<String> = selectors(code).into_iter().map(|(s, _)| s).collect();
+        assert_eq!(found, ["18160ddd", "70a08231", "a9059cbb"]);
+    }
+
+    #[test]
+    fn vyper_buckets() {
+        // a bucket jumps to the next check, or the fallback, when XOR leaves something over
+        let code = "5b 63a9059cbb 81 18 610100 57 6000";
+        assert_eq!(selectors(code), [("a9059cbb".to_string(), 12)]);
+
+        // older vyper loads the selector from memory and negates the comparison
+        let code = "63a9059cbb 6000 51 14 15 610100 57 00";
+        assert_eq!(selectors(code), [("a9059cbb".to_string(), 14)]);
+    }
+
+    #[test]
+    fn comparison_in_function_body() {
+        // `x == 1` in transfer, which starts at 0x11, is no selector
+        let code = "5f35 60e0 1c \
+                    80 63a9059cbb 14 610011 57 \
+                    00 5b \
+                    6001 81 14 610080 57";
+        assert_eq!(selectors(code), [("a9059cbb".to_string(), 0x11)]);
+
+        // without the calldata preamble nothing marks where the dispatcher is
+        assert_eq!(selectors("80 610001 14 610040 57"), []);
+    }
+
+    #[test]
+    fn not_dispatch() {
+        // masks and panic codes are pushed, but never compared and jumped on
+        let code = "63ffffffff 16 634e487b71 60e0 1b 5f 52";
+        assert_eq!(selectors(code), []);
+    }
+}
@@ -0,0 +1,475 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::ast::SourceUnit;
+use crate::lexer::Lexer;
+use crate::parser::{ParseError, Parser};
+use crate::symbols::SymbolTable;
+use crate::vyper::VyperParser;
+
+// prefix=target, or context:prefix=target to only apply to imports made from files under context
+#[derive(Clone, Debug, PartialEq)]
+pub struct Remapping {
+    pub context: Option<String>,
+    pub prefix: String,
+    pub target: String,
+}
+
+impl Remapping {
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let (lhs, target) = line.split_once('=')?;
+        let (context, prefix) = match lhs.split_once(':') {
+            Some((context, prefix)) => (Some(context.to_string()), prefix),
+            None => (None, lhs),
+        };
+
+        if prefix.is_empty() {
+            return None;
+        }
+
+        Some(Remapping {
+            context,
+            prefix: prefix.to_string(),
+            target: target.to_string(),
+        })
+    }
+}
+
+// turns import paths into files on disk the way foundry and hardhat do
+pub struct Resolver {
+    root: PathBuf,
+    remappings: Vec<Remapping>,
+    libs: Vec<PathBuf>,
+}
+
+const PROJECT_MARKERS: [&str; 3] = ["foundry.toml", "remappings.txt", "package.json"];
+
+impl Resolver {
+    pub fn new(root: PathBuf, remappings: Vec<Remapping>, libs: Vec<PathBuf>) -> Self {
+        Resolver {
+            root,
+            remappings,
+            libs,
+        }
+    }
+
+    // the project root is the closest directory holding foundry.toml, remappings.txt or
+    // package.json, falling back to the scanned path itself
+    pub fn for_path(path: &Path) -> Self {
+        let start = if path.is_dir() {
+            path.to_path_buf()
+        } else {
+            path.parent().map(Path::to_path_buf).unwrap_or_default()
+        };
+        let start = start.canonicalize().unwrap_or(start);
+
+        let root = start
+            .ancestors()
+            .find(|dir| PROJECT_MARKERS.iter().any(|m| dir.join(m).is_file()))
+            .unwrap_or(&start)
+            .to_path_buf();
+
+        let mut remappings = Vec::new();
+        let mut libs = vec![PathBuf::from("lib")];
+
+        if let Ok(config) = std::fs::read_to_string(root.join("foundry.toml"))
+            && let Ok(table) = config.parse::<toml::Table>()
+            && let Some(profile) = table
+                .get("profile")
+                .and_then(|p| p.get("default"))
+                .and_then(|p| p.as_table())
+        {
+            if let Some(entries) = profile.get("remappings").and_then(|r| r.as_array()) {
+                remappings.extend(
+                    entries
+                        .iter()
+                        .filter_map(|e| e.as_str())
+                        .filter_map(Remapping::parse),
+                );
+            }
+            if let Some(entries) = profile.get("libs").and_then(|l| l.as_array()) {
+                libs = entries
+                    .iter()
+                    .filter_map(|e| e.as_str())
+                    .map(PathBuf::from)
+                    .collect();
+            }
+        }
+
+        // remappings.txt takes precedence over foundry.toml, as in forge
+        if let Ok(lines) = std::fs::read_to_string(root.join("remappings.txt")) {
+            let mut from_file: Vec<Remapping> =
+                lines.lines().filter_map(Remapping::parse).collect();
+            from_file.append(&mut remappings);
+            remappings = from_file;
+        }
+
+        Resolver::new(root, remappings, libs)
+    }
+
+    pub fn resolve(&self, from: &Path, import: &str) -> Option<PathBuf> {
+        let from = from.canonicalize().unwrap_or_else(|_| from.to_path_buf());
+        let from = from.as_path();
+
+        if import.starts_with("./") || import.starts_with("../") {
+            let dir = from.parent()?;
+            return existing(dir.join(import));
+        }
+
+        if let Some(path) = self.remap(from, import) {
+            return existing(path);
+        }
+
+        // project relative (`src/types/Order.sol`), then library folders, then node_modules
+        // in the root or any of its parents
+        existing(self.root.join(import))
+            .or_else(|| {
+                self.libs
+                    .iter()
+                    .find_map(|lib| existing(self.root.join(lib).join(import)))
+            })
+            .or_else(|| {
+                self.root
+                    .ancestors()
+                    .find_map(|dir| existing(dir.join("node_modules").join(import)))
+            })
+    }
+
+    // longest matching prefix wins, a matching context beats a context-free remapping
+    fn remap(&self, from: &Path, import: &str) -> Option<PathBuf> {
+        let from = from.strip_prefix(&self.root).unwrap_or(from);
+
+        self.remappings
+            .iter()
+            .filter(|r| import.starts_with(&r.prefix))
+            .filter(|r| {
+                r.context
+                    .as_ref()
+                    .is_none_or(|context| from.starts_with(context))
+            })
+            .max_by_key(|r| (r.context.is_some(), r.prefix.len()))
+            .map(|r| {
+                let rest = &import[r.prefix.len()..];
+                self.root.join(format!("{}{}", r.target, rest))
+            })
+    }
+}
+
+fn existing(path: PathBuf) -> Option<PathBuf> {
+    path.canonicalize().ok().filter(|p| p.is_file())
+}
+
+pub fn parse_file(path: &Path, dependency: bool) -> std::io::Result<SourceFile> {
+    let source = std::fs::read_to_string(path)?;
+
+    let extension = path.extension().and_then(|e| e.to_str());
+    if let Some(extension @ ("vy" | "vyi")) = extension {
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        let mut parser = VyperParser::new(&source);
+        let unit = parser.parse_module(name, extension == "vyi");
+        return Ok(SourceFile {
+            path: path.to_path_buf(),
+            symbols: parser.symbols().clone(),
+            diagnostics: parser.diagnostics().to_vec(),
+            source,
+            unit,
+            imports: Vec::new(),
+            dependency,
+        });
+    }
+
+    let mut lexer = Lexer::new(&source);
+    let tokens = lexer.tokenize_spanned();
+
+    let mut parser = Parser::with_spans(tokens);
+    let unit = parser.parse_all_symbols();
+    Ok(SourceFile {
+        path: path.to_path_buf(),
+        symbols: parser.symbols().clone(),
+        diagnostics: parser.diagnostics().to_vec(),
+        source,
+        unit,
+        imports: Vec::new(),
+        dependency,
+    })
+}
+
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub source: String,
+    pub unit: SourceUnit,
+    pub symbols: SymbolTable,
+    // declarations that failed to parse and were skipped
+    pub diagnostics: Vec<ParseError>,
+    // index into `Sources::files` for each of `unit.imports`, None when unresolved
+    pub imports: Vec<Option<usize>>,
+    // only loaded because another file imports it
+    pub dependency: bool,
+}
+
+// the scanned files plus everything they import, transitively
+pub struct Sources {
+    pub files: Vec<SourceFile>,
+    pub warnings: Vec<String>,
+}
+
+impl Sources {
+    pub fn load(paths: &[PathBuf], resolver: &Resolver) -> Self {
+        let mut sources = Sources {
+            files: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let mut loaded: HashMap<PathBuf, usize> = HashMap::new();
+
+        for path in paths {
+            sources.add(path.clone(), false, &mut loaded);
+        }
+
+        // files appended while walking are visited too
+        let mut index = 0;
+        while index < sources.files.len() {
+            let from = sources.files[index].path.clone();
+            let import_paths: Vec<String> = sources.files[index]
+                .unit
+                .imports
+                .iter()
+                .map(|i| i.path.clone())
+                .collect();
+
+            let mut targets = Vec::new();
+            for import in import_paths {
+                let target = match resolver.resolve(&from, &import) {
+                    Some(path) => sources.add(path, true, &mut loaded),
+                    None => {
+                        sources.warnings.push(format!(
+                            "could not resolve import \"{}\" in {}",
+                            import,
+                            from.display()
+                        ));
+                        None
+                    }
+                };
+                targets.push(target);
+            }
+            sources.files[index].imports = targets;
+
+            index += 1;
+        }
+
+        sources
+    }
+
+    fn add(
+        &mut self,
+        path: PathBuf,
+        dependency: bool,
+        loaded: &mut HashMap<PathBuf, usize>,
+    ) -> Option<usize> {
+        let key = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if let Some(&index) = loaded.get(&key) {
+            return Some(index);
+        }
+
+        match parse_file(&path, dependency) {
+            Ok(file) => {
+                let index = self.files.len();
+                self.files.push(file);
+                loaded.insert(key, index);
+                Some(index)
+            }
+            Err(e) => {
+                self.warnings
+                    .push(format!("failed to read {}: {}", path.display(), e));
+                None
+            }
+        }
+    }
+
+    // re-resolves every file's types with the declarations its imports bring into scope
+    pub fn resolve_types(&mut self) {
+        let mut memo = HashMap::new();
+        let tables: Vec<SymbolTable> = (0..self.files.len())
+            .map(|i| self.visible_symbols(i, &mut memo, &mut HashSet::new()))
+            .collect();
+
+        for (file, table) in self.files.iter_mut().zip(tables) {
+            table.resolve_source_unit(&mut file.unit);
+        }
+    }
+
+    // own declarations plus whatever each import exposes; solidity re-exports everything
+    // a file can see, so this recurses through the import graph
+    fn visible_symbols(
+        &self,
+        index: usize,
+        memo: &mut HashMap<usize, SymbolTable>,
+        visiting: &mut HashSet<usize>,
+    ) -> SymbolTable {
+        if let Some(table) = memo.get(&index) {
+            return table.clone();
+        }
+
+        let file = &self.files[index];
+        let mut table = file.symbols.clone();
+
+        // import cycles only see the declarations made so far
+        if !visiting.insert(index) {
+            return table;
+        }
+
+        for (import, target) in file.unit.imports.iter().zip(&file.imports) {
+            let Some(target) = *target else {
+                continue;
+            };
+            let imported = self.visible_symbols(target, memo, visiting);
+
+            if !import.symbols.is_empty() {
+                for (name, alias) in &import.symbols {
+                    table.merge_symbol(&imported, name, alias.as_deref().unwrap_or(name));
+                }
+            } else if let Some(alias) = &import.alias {
+                table.merge_aliased(&imported, alias);
+            } else {
+                table.merge(&imported);
+            }
+        }
+
+        table.resolve_declarations();
+
+        visiting.remove(&index);
+        memo.insert(index, table.clone());
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sift-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(dir: &Path, path: &str, contents: &str) {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn remapping_syntax() {
+        assert_eq!(
+            Remapping::parse("@openzeppelin/=lib/openzeppelin-contracts/"),
+            Some(Remapping {
+                context: None,
+                prefix: "@openzeppelin/".to_string(),
+                target: "lib/openzeppelin-contracts/".to_string(),
+            })
+        );
+        assert_eq!(
+            Remapping::parse("src/:forge-std/=lib/forge-std/src/"),
+            Some(Remapping {
+                context: Some("src/".to_string()),
+                prefix: "forge-std/".to_string(),
+                target: "lib/forge-std/src/".to_string(),
+            })
+        );
+        assert_eq!(Remapping::parse("not a remapping"), None);
+    }
+
+    #[test]
+    fn resolve_project_imports() {
+        let dir = scratch_dir("resolve");
+        write(
+            &dir,
+            "foundry.toml",
+            "[profile.default]\nremappings = [\"@oz/=lib/openzeppelin/contracts/\"]\n",
+        );
+        write(&dir, "remappings.txt", "solmate/=lib/solmate/src/\n");
+        write(&dir, "lib/openzeppelin/contracts/token/IERC20.sol", "");
+        write(&dir, "lib/solmate/src/ERC20.sol", "");
+        write(&dir, "src/types/Order.sol", "");
+        write(&dir, "node_modules/@uniswap/IPool.sol", "");
+        write(&dir, "src/Exchange.sol", "");
+
+        let resolver = Resolver::for_path(&dir.join("src"));
+        let from = dir.join("src/Exchange.sol").canonicalize().unwrap();
+        let root = dir.canonicalize().unwrap();
+
+        let cases = [
+            ("./types/Order.sol", "src/types/Order.sol"),
+            ("src/types/Order.sol", "src/types/Order.sol"),
+            (
+                "@oz/token/IERC20.sol",
+                "lib/openzeppelin/contracts/token/IERC20.sol",
+            ),
+            ("solmate/ERC20.sol", "lib/solmate/src/ERC20.sol"),
+            ("@uniswap/IPool.sol", "node_modules/@uniswap/IPool.sol"),
+        ];
+        for (import, expected) in cases {
+            assert_eq!(
+                resolver.resolve(&from, import),
+                Some(root.join(expected)),
+                "{}",
+                import
+            );
+        }
+        assert_eq!(resolver.resolve(&from, "./Missing.sol"), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn imported_types() {
+        let dir = scratch_dir("types");
+        write(&dir, "foundry.toml", "");
+        write(
+            &dir,
+            "src/types/Order.sol",
+            r#"
+            import "../IERC20.sol";
+            struct Order { address maker; IERC20 token; uint256 amount; }
+            library Types { enum Side { Buy, Sell } }
+            "#,
+        );
+        write(&dir, "src/IERC20.sol", "interface IERC20 {}");
+        write(
+            &dir,
+            "src/Exchange.sol",
+            r#"
+            import {Order as O, Types} from "src/types/Order.sol";
+            import "./IERC20.sol" as Tokens;
+
+            contract Exchange {
+                function submit(O calldata order, Types.Side side) external {}
+                function sweep(Tokens.IERC20 token) external {}
+            }
+            "#,
+        );
+
+        let resolver = Resolver::for_path(&dir.join("src"));
+        let mut sources = Sources::load(&[dir.join("src/Exchange.sol")], &resolver);
+        sources.resolve_types();
+
+        assert!(sources.warnings.is_empty(), "{:?}", sources.warnings);
+        assert_eq!(sources.files.len(), 3);
+        assert!(!sources.files[0].dependency);
+        assert!(sources.files[1].dependency);
+
+        let exchange = &sources.files[0].unit.contracts[0];
+        assert_eq!(
+            exchange.functions[0].signature(),
+            "submit((address,address,uint256),uint8)"
+        );
+        assert_eq!(exchange.functions[1].signature(), "sweep(address)");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
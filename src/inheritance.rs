@@ -0,0 +1,383 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::ast::{ContractDefinition, SourceUnit, SpecialKind, Visibility};
+
+// every contract seen across all parsed files. the same name may be declared in several
+// files, so contracts are told apart by the file declaring them and bases are looked up
+// through that file's imports first
+pub struct Hierarchy<'a> {
+    contracts: Vec<(usize, &'a ContractDefinition)>, // declaring file, definition
+    visible: Vec<Vec<usize>>, // per file: itself, then what it imports, nearest first
+}
+
+// a contract in the linearization, or a base that was never parsed
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Known(usize),
+    Missing(String),
+}
+
+// bases may be written qualified (`Lib.Base`), contracts are only known by their own name
+fn base_name(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+impl<'a> Hierarchy<'a> {
+    // units without an import graph only see their own contracts before any other
+    pub fn new(units: impl IntoIterator<Item = &'a SourceUnit>) -> Self {
+        Self::with_imports(
+            units
+                .into_iter()
+                .map(|unit| (unit, &[] as &[Option<usize>])),
+        )
+    }
+
+    // units along with the index of the unit each of their imports resolved to
+    pub fn with_imports(
+        units: impl IntoIterator<Item = (&'a SourceUnit, &'a [Option<usize>])>,
+    ) -> Self {
+        let mut contracts = Vec::new();
+        let mut imports = Vec::new();
+        for (file, (unit, unit_imports)) in units.into_iter().enumerate() {
+            contracts.extend(unit.contracts.iter().map(|c| (file, c)));
+            imports.push(unit_imports.iter().flatten().copied().collect::<Vec<_>>());
+        }
+
+        // breadth first, so a direct import shadows what it imports itself
+        let visible = (0..imports.len())
+            .map(|file| {
+                let mut order = vec![file];
+                let mut queue = VecDeque::from([file]);
+                while let Some(current) = queue.pop_front() {
+                    for &next in &imports[current] {
+                        if !order.contains(&next) {
+                            order.push(next);
+                            queue.push_back(next);
+                        }
+                    }
+                }
+                order
+            })
+            .collect();
+
+        Hierarchy { contracts, visible }
+    }
+
+    // any contract of that name, for when it does not matter which
+    pub fn get(&self, name: &str) -> Option<&'a ContractDefinition> {
+        let name = base_name(name);
+        self.contracts
+            .iter()
+            .find(|(_, c)| c.name == name)
+            .map(|(_, c)| *c)
+    }
+
+    fn index_of(&self, contract: &ContractDefinition) -> Option<usize> {
+        self.contracts
+            .iter()
+            .position(|(_, c)| std::ptr::eq(*c, contract))
+            .or_else(|| self.contracts.iter().position(|(_, c)| *c == contract))
+    }
+
+    // the base `name` as seen from the file declaring `from`, falling back to any contract
+    // of that name when the imports do not lead to it
+    fn resolve(&self, from: usize, name: &str) -> Node {
+        let name = base_name(name);
+        let (file, _) = self.contracts[from];
+
+        self.visible[file]
+            .iter()
+            .find_map(|&f| {
+                self.contracts
+                    .iter()
+                    .position(|(cf, c)| *cf == f && c.name == name)
+            })
+            .or_else(|| self.contracts.iter().position(|(_, c)| c.name == name))
+            .map_or_else(|| Node::Missing(name.to_string()), Node::Known)
+    }
+
+    fn name(&self, node: &Node) -> String {
+        match node {
+            Node::Known(index) => self.contracts[*index].1.name.clone(),
+            Node::Missing(name) => name.clone(),
+        }
+    }
+
+    // C3 linearization, most derived first. solidity lists bases from "most base-like"
+    // to "most derived", so they are merged right to left. bases that were never parsed
+    // are kept as leaves without members.
+    pub fn linearize(&self, contract: &ContractDefinition) -> Result<Vec<String>, String> {
+        let nodes = self.linearize_contract(contract)?;
+        Ok(nodes.iter().map(|n| self.name(n)).collect())
+    }
+
+    fn linearize_contract(&self, contract: &ContractDefinition) -> Result<Vec<Node>, String> {
+        match self.index_of(contract) {
+            Some(index) => self.linearize_inner(Node::Known(index), &mut Vec::new()),
+            None => Err(format!("contract {} was not parsed", contract.name)),
+        }
+    }
+
+    fn linearize_inner(&self, node: Node, stack: &mut Vec<Node>) -> Result<Vec<Node>, String> {
+        if stack.contains(&node) {
+            return Err(format!("cyclic inheritance involving {}", self.name(&node)));
+        }
+
+        let bases: Vec<Node> = match node {
+            Node::Known(index) => self.contracts[index]
+                .1
+                .bases
+                .iter()
+                .rev()
+                .map(|b| self.resolve(index, b))
+                .collect(),
+            Node::Missing(_) => Vec::new(),
+        };
+
+        stack.push(node.clone());
+        let mut sequences = Vec::new();
+        for base in &bases {
+            sequences.push(self.linearize_inner(base.clone(), stack)?);
+        }
+        stack.pop();
+        sequences.push(bases);
+
+        let mut result = vec![node.clone()];
+        loop {
+            sequences.retain(|s| !s.is_empty());
+            if sequences.is_empty() {
+                break;
+            }
+
+            // first head that does not appear in the tail of any other sequence
+            let candidate = sequences
+                .iter()
+                .map(|s| &s[0])
+                .find(|head| !sequences.iter().any(|s| s[1..].contains(head)))
+                .cloned()
+                .ok_or_else(|| format!("linearization of {} is impossible", self.name(&node)))?;
+
+            for sequence in sequences.iter_mut() {
+                if sequence[0] == candidate {
+                    sequence.remove(0);
+                }
+            }
+            result.push(candidate);
+        }
+
+        Ok(result)
+    }
+
+    // the contract with every public/external member it inherits, walking the linearization
+    // from most derived to most base so overriding definitions shadow the ones they override
+    pub fn flatten(&self, contract: &ContractDefinition) -> Result<ContractDefinition, String> {
+        let mut flat = ContractDefinition {
+            functions: Vec::new(),
+            variables: Vec::new(),
+            events: Vec::new(),
+            errors: Vec::new(),
+            // constructors are not inherited, fallback and receive are
+            special_functions: contract
+                .special_functions
+                .iter()
+                .filter(|f| f.kind == SpecialKind::Constructor)
+                .cloned()
+                .collect(),
+            ..contract.clone()
+        };
+
+        // functions and getters share one namespace, a public variable can override a function
+        let mut seen_functions = HashSet::new();
+        let mut seen_events = HashSet::new();
+        let mut seen_errors = HashSet::new();
+
+        // the contract itself comes first, with its own members rather than those of
+        // another contract of the same name
+        for (position, node) in self.linearize_contract(contract)?.iter().enumerate() {
+            let def = match node {
+                _ if position == 0 => contract,
+                Node::Known(index) => self.contracts[*index].1,
+                Node::Missing(_) => continue,
+            };
+
+            for func in &def.functions {
+                if matches!(func.visibility, Visibility::External | Visibility::Public)
+                    && seen_functions.insert(func.signature())
+                {
+                    flat.functions.push(func.clone());
+                }
+            }
+            for var in &def.variables {
+                if var.visibility == Visibility::Public && seen_functions.insert(var.signature()) {
+                    flat.variables.push(var.clone());
+                }
+            }
+            for event in &def.events {
+                if seen_events.insert(event.signature()) {
+                    flat.events.push(event.clone());
+                }
+            }
+            for error in &def.errors {
+                if seen_errors.insert(error.signature()) {
+                    flat.errors.push(error.clone());
+                }
+            }
+            for func in &def.special_functions {
+                if func.kind != SpecialKind::Constructor
+                    && flat.special_function(func.kind).is_none()
+                {
+                    flat.special_functions.push(func.clone());
+                }
+            }
+        }
+
+        Ok(flat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn parse(input: &str) -> SourceUnit {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse_all_symbols()
+    }
+
+    #[test]
+    fn linearization() {
+        let unit = parse(
+            r#"
+            contract O {}
+            contract A is O {}
+            contract B is O {}
+            contract C is O {}
+            contract D is O {}
+            contract E is O {}
+            contract K1 is C, B, A {}
+            contract K2 is E, B, D {}
+            contract K3 is D, A {}
+            contract Z is K3, K2, K1 {}
+        "#,
+        );
+        let hierarchy = Hierarchy::new([&unit]);
+
+        assert_eq!(
+            hierarchy.linearize(&unit.contracts[9]).unwrap(),
+            vec!["Z", "K1", "K2", "K3", "A", "D", "B", "C", "E", "O"]
+        );
+    }
+
+    #[test]
+    fn impossible_linearization() {
+        let unit = parse(
+            r#"
+            contract X {}
+            contract A is X {}
+            contract B is A, X {}
+        "#,
+        );
+        let hierarchy = Hierarchy::new([&unit]);
+
+        assert!(hierarchy.linearize(&unit.contracts[2]).is_err());
+    }
+
+    #[test]
+    fn flatten_across_files() {
+        let interface = parse(
+            r#"
+            interface IERC20 {
+                event Transfer(address indexed from, address indexed to, uint256 value);
+                function totalSupply() external view returns (uint256);
+                function transfer(address to, uint256 amount) external returns (bool);
+            }
+        "#,
+        );
+        let token = parse(
+            r#"
+            abstract contract Ownable {
+                address public owner;
+                function transferOwnership(address newOwner) public virtual {}
+                function _check() internal view {}
+            }
+
+            contract ERC20 is IERC20, Ownable, Missing {
+                uint256 public override totalSupply;
+                function transfer(address to, uint256 amount) external override returns (bool) {}
+                function transferOwnership(address newOwner) public override {}
+            }
+        "#,
+        );
+        let hierarchy = Hierarchy::new([&interface, &token]);
+        let erc20 = &token.contracts[1];
+
+        assert_eq!(
+            hierarchy.linearize(erc20).unwrap(),
+            vec!["ERC20", "Missing", "Ownable", "IERC20"]
+        );
+
+        let flat = hierarchy.flatten(erc20).unwrap();
+        let functions: Vec<String> = flat.functions.iter().map(|f| f.signature()).collect();
+        let variables: Vec<String> = flat.variables.iter().map(|v| v.signature()).collect();
+
+        assert_eq!(
+            functions,
+            vec!["transfer(address,uint256)", "transferOwnership(address)"]
+        );
+        assert_eq!(variables, vec!["totalSupply()", "owner()"]);
+        assert_eq!(flat.events.len(), 1);
+        assert_eq!(flat.bases, erc20.bases);
+    }
+
+    #[test]
+    fn inherited_fallback_but_not_constructor() {
+        let unit = parse(
+            r#"
+            contract Base {
+                constructor(uint256 x) {}
+                receive() external payable {}
+            }
+            contract Derived is Base {
+                constructor() Base(1) {}
+            }
+            contract Plain is Base {}
+        "#,
+        );
+        let hierarchy = Hierarchy::new([&unit]);
+
+        let derived = hierarchy.flatten(&unit.contracts[1]).unwrap();
+        assert!(derived.special_function(SpecialKind::Receive).is_some());
+        let constructor = derived.special_function(SpecialKind::Constructor).unwrap();
+        assert!(constructor.parameters.is_empty());
+
+        let plain = hierarchy.flatten(&unit.contracts[2]).unwrap();
+        assert!(plain.special_function(SpecialKind::Receive).is_some());
+        assert!(plain.special_function(SpecialKind::Constructor).is_none());
+    }
+
+    #[test]
+    fn same_name_in_several_files() {
+        let a = parse("contract Token { function a() external {} }");
+        let b = parse("contract Token { function b() external {} }");
+        let hierarchy = Hierarchy::new([&a, &b]);
+
+        let flat = hierarchy.flatten(&b.contracts[0]).unwrap();
+        let functions: Vec<String> = flat.functions.iter().map(|f| f.signature()).collect();
+        assert_eq!(functions, ["b()"]);
+
+        // bases are looked up through the imports of the file declaring the contract
+        let base_a = parse("contract Base { function fromA() external {} }");
+        let base_b = parse("contract Base { function fromB() external {} }");
+        let derived = parse("contract Derived is Base {}");
+        let imports: [&[Option<usize>]; 3] = [&[], &[], &[Some(1)]];
+        let hierarchy =
+            Hierarchy::with_imports([&base_a, &base_b, &derived].into_iter().zip(imports));
+
+        let flat = hierarchy.flatten(&derived.contracts[0]).unwrap();
+        assert_eq!(flat.functions[0].signature(), "fromB()");
+    }
+}
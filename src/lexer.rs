@@ -1,6 +1,24 @@
+use crate::span::Span;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
+    Pragma(String), // pragma solidity ^0.8.0, everything up to the semicolon
+    Import,
+    As,
     Contract,
+    Abstract,
+    Interface,
+    Library,
+    Struct,
+    Enum,
+    TypeDef, // `type Price is uint128`
+    Is,
+    Event,
+    Indexed,
+    Anonymous,
+    Constructor,
+    Modifier,
+    Using,
     Function,
     Mapping,
     Public,
@@ -10,6 +28,8 @@ pub enum Token {
     View,
     Pure,
     Payable,
+    Virtual,
+    Override,
     Returns,
     Memory,
     Calldata,
@@ -24,10 +44,13 @@ pub enum Token {
     RightBracket, // ]
     Comma,        // ,
     Semicolon,    // ;
+    Dot,          // .
     Arrow,        // =>
 
     Identifier(String),
     Number(String),
+    StringLiteral(String), // "..." and unicode"...", escapes decoded
+    HexLiteral(String),    // hex"00ff", just the digits
 
     Type(String), // uint256, address, etc.
 
@@ -39,6 +62,9 @@ pub struct Lexer {
     input: Vec<char>,
     position: usize,
     current: Option<char>,
+    offset: usize, // byte offset of `current`
+    line: usize,
+    column: usize,
 }
 
 impl Lexer {
@@ -50,10 +76,22 @@ impl Lexer {
             input: chars,
             position: 0,
             current,
+            offset: 0,
+            line: 1,
+            column: 1,
         }
     }
 
     fn advance(&mut self) {
+        if let Some(ch) = self.current {
+            self.offset += ch.len_utf8();
+            if ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
         self.position += 1;
         self.current = self.input.get(self.position).copied();
     }
@@ -116,6 +154,91 @@ impl Lexer {
         identifier
     }
 
+    // contents of a quoted string, without the quotes and with escape sequences decoded
+    fn read_string(&mut self, quote: char) -> String {
+        let mut value = String::new();
+        self.advance(); // opening quote
+
+        while let Some(ch) = self.current {
+            if ch == quote {
+                self.advance();
+                break;
+            }
+            self.advance();
+            if ch == '\\' {
+                self.read_escape(&mut value);
+            } else {
+                value.push(ch);
+            }
+        }
+
+        value
+    }
+
+    // the part of an escape sequence after the backslash; unknown escapes are kept as written
+    fn read_escape(&mut self, value: &mut String) {
+        let Some(ch) = self.current else {
+            return;
+        };
+        self.advance();
+
+        match ch {
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            '\\' | '\'' | '"' => value.push(ch),
+            // a backslash before a line break continues the string on the next line
+            '\n' => {}
+            'x' => match self.read_hex_digits(2) {
+                Some(byte) => value.push(char::from(byte as u8)),
+                None => value.push_str("\\x"),
+            },
+            'u' => match self.read_hex_digits(4).and_then(char::from_u32) {
+                Some(c) => value.push(c),
+                None => value.push_str("\\u"),
+            },
+            _ => {
+                value.push('\\');
+                value.push(ch);
+            }
+        }
+    }
+
+    fn read_hex_digits(&mut self, count: usize) -> Option<u32> {
+        let digits: String = self
+            .input
+            .get(self.position..self.position + count)?
+            .iter()
+            .collect();
+        let value = u32::from_str_radix(&digits, 16).ok()?;
+        for _ in 0..count {
+            self.advance();
+        }
+        Some(value)
+    }
+
+    // hex"00ff_ee" or hex'00ff', underscores separate digit pairs
+    fn read_hex_string(&mut self, quote: char) -> String {
+        self.read_string(quote)
+            .chars()
+            .filter(|c| *c != '_')
+            .collect()
+    }
+
+    fn read_until(&mut self, end: char) -> String {
+        let mut value = String::new();
+
+        while let Some(ch) = self.current {
+            if ch == end {
+                break;
+            }
+            value.push(ch);
+            self.advance();
+        }
+
+        value
+    }
+
     fn read_number(&mut self) -> String {
         let mut number = String::new();
 
@@ -132,6 +255,26 @@ impl Lexer {
     }
 
     pub fn next_token(&mut self) -> Token {
+        self.next_spanned().0
+    }
+
+    // the next token along with where it was found
+    pub fn next_spanned(&mut self) -> (Token, Span) {
+        self.skip_trivia();
+
+        let (offset, line, column) = (self.offset, self.line, self.column);
+        let token = self.read_token();
+        let span = Span {
+            offset,
+            len: self.offset - offset,
+            line,
+            column,
+        };
+
+        (token, span)
+    }
+
+    fn skip_trivia(&mut self) {
         loop {
             self.skip_whitespace();
 
@@ -145,7 +288,9 @@ impl Lexer {
                 break;
             }
         }
+    }
 
+    fn read_token(&mut self) -> Token {
         let ch = match self.current {
             Some(c) => c,
             None => return Token::Eof,
@@ -184,6 +329,11 @@ impl Lexer {
                 self.advance();
                 Token::Semicolon
             }
+            '.' => {
+                self.advance();
+                Token::Dot
+            }
+            '"' | '\'' => Token::StringLiteral(self.read_string(ch)),
             '=' => {
                 self.advance();
                 if self.current == Some('>') {
@@ -197,8 +347,33 @@ impl Lexer {
             _ if ch.is_alphabetic() || ch == '_' => {
                 let ident = self.read_identifier();
 
+                // string literal prefixes, only when the quote follows immediately
+                if let Some(quote @ ('"' | '\'')) = self.current {
+                    match ident.as_str() {
+                        "hex" => return Token::HexLiteral(self.read_hex_string(quote)),
+                        "unicode" => return Token::StringLiteral(self.read_string(quote)),
+                        _ => {}
+                    }
+                }
+
                 match ident.as_str() {
+                    "pragma" => Token::Pragma(self.read_until(';').trim().to_string()),
+                    "import" => Token::Import,
+                    "as" => Token::As,
                     "contract" => Token::Contract,
+                    "abstract" => Token::Abstract,
+                    "interface" => Token::Interface,
+                    "library" => Token::Library,
+                    "struct" => Token::Struct,
+                    "enum" => Token::Enum,
+                    "type" => Token::TypeDef,
+                    "is" => Token::Is,
+                    "event" => Token::Event,
+                    "indexed" => Token::Indexed,
+                    "anonymous" => Token::Anonymous,
+                    "constructor" => Token::Constructor,
+                    "modifier" => Token::Modifier,
+                    "using" => Token::Using,
                     "function" => Token::Function,
                     "mapping" => Token::Mapping,
                     "external" => Token::External,
@@ -208,22 +383,15 @@ impl Lexer {
                     "view" => Token::View,
                     "pure" => Token::Pure,
                     "payable" => Token::Payable,
+                    "virtual" => Token::Virtual,
+                    "override" => Token::Override,
                     "returns" => Token::Returns,
                     "memory" => Token::Memory,
                     "calldata" => Token::Calldata,
                     "storage" => Token::Storage,
                     "return" => Token::Return,
 
-                    // types
-                    s if s.starts_with("uint")
-                        || s.starts_with("int")
-                        || s.starts_with("bytes")
-                        || s == "address"
-                        || s == "bool"
-                        || s == "string" =>
-                    {
-                        Token::Type(ident)
-                    }
+                    s if is_elementary_type_name(s) => Token::Type(ident),
 
                     _ => Token::Identifier(ident),
                 }
@@ -254,6 +422,44 @@ impl Lexer {
 
         tokens
     }
+
+    pub fn tokenize_spanned(&mut self) -> Vec<(Token, Span)> {
+        let mut tokens = Vec::new();
+
+        loop {
+            let (token, span) = self.next_spanned();
+            let end = token == Token::Eof;
+            tokens.push((token, span));
+            if end {
+                break;
+            }
+        }
+
+        tokens
+    }
+}
+
+// anything shaped like an elementary type, so `uint7` is reported as an invalid type rather
+// than taken for a user-defined one. `interval` and `bytesLeft` stay identifiers
+fn is_elementary_type_name(name: &str) -> bool {
+    let digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    if matches!(name, "address" | "bool" | "string" | "byte") {
+        return true;
+    }
+    if let Some(size) = name
+        .strip_prefix("ufixed")
+        .or_else(|| name.strip_prefix("fixed"))
+    {
+        return size.is_empty()
+            || size
+                .split_once('x')
+                .is_some_and(|(m, n)| digits(m) && digits(n));
+    }
+    ["uint", "int", "bytes"].iter().any(|prefix| {
+        name.strip_prefix(prefix)
+            .is_some_and(|size| size.is_empty() || digits(size))
+    })
 }
 
 #[cfg(test)]
@@ -343,6 +549,113 @@ mod tests {
         );
     }
 
+    #[test]
+    fn imports() {
+        let input = r#"import {Order as O} from "src/types/Order.sol"; import './IERC20.sol';"#;
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+
+        assert_eq!(
+            tokens,
+            Vec::from([
+                Token::Import,
+                Token::LeftBrace,
+                Token::Identifier("Order".to_string()),
+                Token::As,
+                Token::Identifier("O".to_string()),
+                Token::RightBrace,
+                Token::Identifier("from".to_string()),
+                Token::StringLiteral("src/types/Order.sol".to_string()),
+                Token::Semicolon,
+                Token::Import,
+                Token::StringLiteral("./IERC20.sol".to_string()),
+                Token::Semicolon,
+                Token::Eof,
+            ])
+        );
+    }
+
+    #[test]
+    fn string_literals() {
+        let input = r#"require(ok, "}function{"); x = 'it\'s\n'; y = hex"00ff_ee"; z = unicode"caf\u00e9 ☕"; "a\x41\"";"#;
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+
+        let literals: Vec<Token> = tokens
+            .into_iter()
+            .filter(|t| matches!(t, Token::StringLiteral(_) | Token::HexLiteral(_)))
+            .collect();
+        assert_eq!(
+            literals,
+            Vec::from([
+                Token::StringLiteral("}function{".to_string()),
+                Token::StringLiteral("it's\n".to_string()),
+                Token::HexLiteral("00ffee".to_string()),
+                Token::StringLiteral("café ☕".to_string()),
+                Token::StringLiteral("aA\"".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let input = "contract A {\n  function \"é\" f();\n}";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize_spanned();
+
+        let (token, span) = &tokens[3];
+        assert_eq!(token, &Token::Function);
+        assert_eq!(
+            (span.offset, span.len, span.line, span.column),
+            (15, 8, 2, 3)
+        );
+
+        // byte offsets count the two bytes of é, columns count it once
+        let (token, span) = &tokens[5];
+        assert_eq!(token, &Token::Identifier("f".to_string()));
+        assert_eq!((span.offset, span.line, span.column), (29, 2, 16));
+
+        let (token, span) = tokens.last().unwrap();
+        assert_eq!(token, &Token::Eof);
+        assert_eq!((span.line, span.column), (3, 2));
+    }
+
+    #[test]
+    fn elementary_types() {
+        let input =
+            "uint8 int uint7 bytes32 fixed128x18 ufixed interval bytesLeft fixedPoint uintx";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+
+        let types: Vec<&Token> = tokens
+            .iter()
+            .filter(|t| matches!(t, Token::Type(_)))
+            .collect();
+        assert_eq!(types.len(), 6);
+        assert_eq!(tokens[6], Token::Identifier("interval".to_string()));
+        assert_eq!(tokens[7], Token::Identifier("bytesLeft".to_string()));
+        assert_eq!(tokens[8], Token::Identifier("fixedPoint".to_string()));
+        assert_eq!(tokens[9], Token::Identifier("uintx".to_string()));
+    }
+
+    #[test]
+    fn pragma() {
+        let input = "pragma solidity >=0.4.22 <0.9.0; pragma abicoder v2;";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+
+        assert_eq!(
+            tokens,
+            Vec::from([
+                Token::Pragma("solidity >=0.4.22 <0.9.0".to_string()),
+                Token::Semicolon,
+                Token::Pragma("abicoder v2".to_string()),
+                Token::Semicolon,
+                Token::Eof,
+            ])
+        );
+    }
+
     #[test]
     fn comments() {
         let input = r#"
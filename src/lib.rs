@@ -1,5 +1,15 @@
+pub mod abi;
+pub mod artifacts;
 pub mod ast;
+pub mod db;
+pub mod evm;
+pub mod imports;
+pub mod inheritance;
 pub mod lexer;
 pub mod output;
 pub mod parser;
 pub mod selector;
+pub mod span;
+pub mod symbols;
+pub mod version;
+pub mod vyper;
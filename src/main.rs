@@ -1,96 +1,770 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-mod ast;
-mod lexer;
-mod output;
-mod parser;
-mod selector;
-
-use crate::lexer::Lexer;
-use crate::output::FunctionOutput;
-use crate::output::output_json;
-use crate::output::output_tsv;
-use crate::parser::Parser as SolidityParser;
+use sift::abi::ContractAbi;
+use sift::artifacts::Artifact;
+use sift::ast::{ContractKind, SourceUnit};
+use sift::db::Database;
+use sift::evm::{decode_hex, disassemble, find_selectors};
+use sift::imports::{Resolver, Sources};
+use sift::inheritance::Hierarchy;
+use sift::output::output_json;
+use sift::output::{
+    ContractOutput, DispatchOutput, FunctionOutput, InterfaceOutput, Kind, LookupOutput,
+};
+use sift::output::{
+    output_contracts_tsv, output_dispatch_tsv, output_entries_tsv, output_interfaces_tsv,
+    output_json_with_contracts, output_tsv,
+};
+use sift::selector::{format_selector, normalize_selector};
+use sift::span::{Span, code_frame};
 
 #[derive(Parser)]
-#[command(name = "sift")]
+#[command(
+    name = "sift",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Cli {
-    #[arg(value_name = "PATH")]
-    path: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    #[arg(short, long)]
+    #[arg(value_name = "PATH", required = true)]
+    path: Option<PathBuf>,
+
+    /// Shorthand for --format json
+    #[arg(short, long, conflicts_with = "format")]
     json: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Tsv)]
+    format: Format,
+
+    /// List every contract with the members it inherits from its bases
+    #[arg(short, long)]
+    inherited: bool,
+
+    /// Also list each contract's constructor arguments and whether it has fallback and receive functions
+    #[arg(short, long)]
+    special: bool,
+
+    /// Fail instead of skipping declarations that cannot be parsed
+    #[arg(long)]
+    strict: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    /// Aligned columns
+    Tsv,
+    /// An array of rows
+    Json,
+    /// The ABI of every contract, as solc generates it
+    Abi,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the ERC-165 interface id of every interface, as type(I).interfaceId
+    InterfaceId {
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+
+        #[arg(short, long)]
+        json: bool,
+
+        /// Fail instead of skipping declarations that cannot be parsed
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// Find the signatures behind a 4-byte selector or a 32-byte event topic
+    Lookup {
+        #[arg(value_name = "SELECTOR")]
+        selector: String,
+
+        /// Database file to search
+        #[arg(long, value_name = "FILE", default_value = "sift.db")]
+        db: PathBuf,
+
+        /// Scan a path on the fly instead of searching the database
+        #[arg(long, value_name = "PATH")]
+        scan: Option<PathBuf>,
+
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// List the selectors a contract dispatches on, from its hex-encoded bytecode
+    Bytecode {
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Resolve the selectors against a database
+        #[arg(long, value_name = "FILE")]
+        db: Option<PathBuf>,
+
+        /// Print every instruction instead
+        #[arg(short, long)]
+        disassemble: bool,
+
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Maintain a local SQLite database of selectors
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+
+        /// Database file, created when missing
+        #[arg(long, value_name = "FILE", default_value = "sift.db", global = true)]
+        db: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Add the selectors, events and errors found under a path
+    Add {
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+
+        /// Also add the members every contract inherits from its bases
+        #[arg(short, long)]
+        inherited: bool,
+
+        /// Fail instead of skipping declarations that cannot be parsed
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// List the entries whose selector starts with TERM or whose signature contains it
+    Query {
+        #[arg(value_name = "TERM")]
+        term: Option<String>,
+
+        #[arg(short, long)]
+        kind: Option<Kind>,
+
+        #[arg(short, long)]
+        json: bool,
+    },
 }
 
 fn main() {
     let cli = Cli::parse();
 
-    let files = if cli.path.is_dir() {
-        find_solidity_files(&cli.path)
-    } else if cli.path.is_file() {
-        vec![cli.path.clone()]
+    match &cli.command {
+        Some(Command::InterfaceId { path, json, strict }) => interface_ids(path, *json, *strict),
+        Some(Command::Lookup {
+            selector,
+            db,
+            scan,
+            json,
+        }) => lookup(selector, db, scan.as_deref(), *json),
+        Some(Command::Bytecode {
+            file,
+            db,
+            disassemble,
+            json,
+        }) => bytecode(file, db.as_deref(), *disassemble, *json),
+        Some(Command::Db { command, db }) => database(command, db),
+        None => list(&cli),
+    }
+}
+
+// the scanned files and everything they import, with types resolved
+fn load_sources(path: &Path, strict: bool) -> Sources {
+    let files = if path.is_dir() {
+        find_source_files(path)
+    } else if is_artifact_file(path) {
+        Vec::new()
+    } else if path.is_file() {
+        vec![path.to_path_buf()]
     } else {
-        eprintln!("error: path does not exist: {}", cli.path.display());
+        eprintln!("error: path does not exist: {}", path.display());
         std::process::exit(1);
     };
 
-    let mut all_functions = Vec::new();
+    // imported files are loaded for their declarations but not listed themselves
+    let resolver = Resolver::for_path(path);
+    let mut sources = Sources::load(&files, &resolver);
+    for warning in &sources.warnings {
+        eprintln!("warning: {}", warning);
+    }
+    report_diagnostics(&sources, strict);
+    sources.resolve_types();
 
-    for file in files {
-        match extract_functions_from_file(&file) {
-            Ok(mut functions) => {
-                functions.retain(|f| f.visibility == "external" || f.visibility == "public");
-                all_functions.extend(functions);
-            }
-            Err(e) => {
-                eprintln!("warning: failed to parse {}: {}", file.display(), e);
+    sources
+}
+
+fn interface_ids(path: &Path, json: bool, strict: bool) {
+    let sources = load_sources(path, strict);
+
+    let mut interfaces = Vec::new();
+    for source in sources.files.iter().filter(|f| !f.dependency) {
+        let file = source.path.display().to_string();
+        interfaces.extend(
+            source
+                .unit
+                .contracts
+                .iter()
+                .filter_map(|c| InterfaceOutput::from_contract(c, &file)),
+        );
+    }
+
+    if json {
+        if let Err(e) = output_json(&interfaces) {
+            eprintln!("error formatting JSON: {}", e);
+            std::process::exit(1);
+        }
+    } else {
+        output_interfaces_tsv(&interfaces);
+    }
+}
+
+fn list(cli: &Cli) {
+    let Some(path) = &cli.path else {
+        return;
+    };
+    let format = if cli.json { Format::Json } else { cli.format };
+    if format == Format::Abi {
+        return abis(path, cli.strict);
+    }
+    let (all_functions, all_contracts) = collect(path, cli.inherited, cli.strict);
+
+    if format == Format::Json {
+        let result = if cli.special {
+            output_json_with_contracts(&all_functions, &all_contracts)
+        } else {
+            output_json(&all_functions)
+        };
+        if let Err(e) = result {
+            eprintln!("error formatting JSON: {}", e);
+            std::process::exit(1);
+        }
+    } else {
+        output_tsv(&all_functions);
+        if cli.special {
+            output_contracts_tsv(&all_contracts);
+        }
+    }
+}
+
+// an ABI includes everything a contract inherits, so contracts are always flattened
+fn abis(path: &Path, strict: bool) {
+    let sources = load_sources(path, strict);
+    let hierarchy = Hierarchy::with_imports(
+        sources
+            .files
+            .iter()
+            .map(|f| (&f.unit, f.imports.as_slice())),
+    );
+
+    let mut abis = Vec::new();
+    for source in sources.files.iter().filter(|f| !f.dependency) {
+        let unit = flatten_unit(&hierarchy, &source.unit);
+        let file = source.path.display().to_string();
+        abis.extend(
+            unit.contracts
+                .iter()
+                .map(|c| ContractAbi::from_contract(c, &file)),
+        );
+    }
+
+    if let Err(e) = output_json(&abis) {
+        eprintln!("error formatting JSON: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn bytecode(file: &Path, db: Option<&Path>, listing: bool, json: bool) {
+    let code = std::fs::read_to_string(file)
+        .map_err(|e| e.to_string())
+        .and_then(|text| decode_hex(&text).map_err(|e| e.to_string()));
+    let code = code.unwrap_or_else(|e| {
+        eprintln!("error: cannot read bytecode from {}: {}", file.display(), e);
+        std::process::exit(1);
+    });
+
+    if listing {
+        for instruction in disassemble(&code) {
+            if instruction.immediate.is_empty() {
+                println!("{:06x} {}", instruction.offset, instruction.mnemonic());
+            } else {
+                println!(
+                    "{:06x} {} 0x{}",
+                    instruction.offset,
+                    instruction.mnemonic(),
+                    hex::encode(instruction.immediate)
+                );
             }
         }
+        return;
+    }
+
+    let db = db.map(|path| {
+        if !path.is_file() {
+            eprintln!("error: {} does not exist", path.display());
+            std::process::exit(1);
+        }
+        Database::open(path).unwrap_or_else(|e| {
+            eprintln!("error: cannot open {}: {}", path.display(), e);
+            std::process::exit(1);
+        })
+    });
+
+    let mut entries = Vec::new();
+    for entry in find_selectors(&code) {
+        let selector = format_selector(&entry.selector);
+        let signatures = match &db {
+            Some(db) => match db.lookup(&selector) {
+                Ok(found) => found.into_iter().map(|e| e.signature).collect(),
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    std::process::exit(1);
+                }
+            },
+            None => Vec::new(),
+        };
+        entries.push(DispatchOutput {
+            selector,
+            entry: format!("0x{:04x}", entry.entry),
+            signatures,
+        });
+    }
+    if entries.is_empty() {
+        eprintln!("warning: no dispatcher found in {}", file.display());
     }
 
-    if cli.json {
-        if let Err(e) = output_json(&all_functions) {
+    if json {
+        if let Err(e) = output_json(&entries) {
             eprintln!("error formatting JSON: {}", e);
             std::process::exit(1);
         }
     } else {
-        output_tsv(&all_functions)
+        output_dispatch_tsv(&entries);
+    }
+}
+
+fn database(command: &DbCommand, path: &Path) {
+    let mut db = Database::open(path).unwrap_or_else(|e| {
+        eprintln!("error: cannot open {}: {}", path.display(), e);
+        std::process::exit(1);
+    });
+
+    let result = match command {
+        DbCommand::Add {
+            path: source,
+            inherited,
+            strict,
+        } => {
+            let (functions, _) = collect(source, *inherited, *strict);
+            db.add(&functions).map(|added| {
+                println!(
+                    "added {} new entries to {}, {} already known",
+                    added.new,
+                    path.display(),
+                    added.known
+                );
+            })
+        }
+        DbCommand::Query { term, kind, json } => db.query(term.as_deref(), *kind).map(|entries| {
+            if !*json {
+                output_entries_tsv(&entries);
+            } else if let Err(e) = output_json(&entries) {
+                eprintln!("error formatting JSON: {}", e);
+                std::process::exit(1);
+            }
+        }),
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}: {}", path.display(), e);
+        std::process::exit(1);
     }
 }
 
-fn find_solidity_files(dir: &Path) -> Vec<PathBuf> {
+fn lookup(selector: &str, db: &Path, scan: Option<&Path>, json: bool) {
+    let Some(selector) = normalize_selector(selector) else {
+        eprintln!(
+            "error: {} is neither a 4-byte selector nor a 32-byte topic",
+            selector
+        );
+        std::process::exit(1);
+    };
+
+    let matches: Vec<FunctionOutput> = match scan {
+        Some(path) => {
+            let (functions, _) = collect(path, false, false);
+            functions
+                .into_iter()
+                .filter(|f| f.selector == selector)
+                .collect()
+        }
+        None => {
+            // opening would create an empty database
+            if !db.is_file() {
+                eprintln!(
+                    "error: {} does not exist, create it with `sift db add` or use --scan",
+                    db.display()
+                );
+                std::process::exit(1);
+            }
+            let entries = Database::open(db).and_then(|db| db.lookup(&selector));
+            match entries {
+                Ok(entries) => entries.into_iter().map(FunctionOutput::from).collect(),
+                Err(e) => {
+                    eprintln!("error: {}: {}", db.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    };
+    let found = !matches.is_empty();
+    let output = LookupOutput::new(selector, matches);
+
+    if json {
+        if let Err(e) = output_json(&output) {
+            eprintln!("error formatting JSON: {}", e);
+            std::process::exit(1);
+        }
+    } else if found {
+        output_tsv(&output.matches);
+    } else {
+        eprintln!("no known signature for {}", output.selector);
+    }
+    if output.collision {
+        eprintln!(
+            "warning: collision, {} is shared by several signatures",
+            output.selector
+        );
+    }
+
+    if !found {
+        std::process::exit(1);
+    }
+}
+
+// the public rows and the contracts of every scanned file
+fn collect(
+    path: &Path,
+    inherited: bool,
+    strict: bool,
+) -> (Vec<FunctionOutput>, Vec<ContractOutput>) {
+    let sources = load_sources(path, strict);
+
+    // bases can live in any of the scanned or imported files
+    let hierarchy = Hierarchy::with_imports(
+        sources
+            .files
+            .iter()
+            .map(|f| (&f.unit, f.imports.as_slice())),
+    );
+
+    let mut all_functions = Vec::new();
+    let mut all_contracts = Vec::new();
+
+    for source in sources.files.iter().filter(|f| !f.dependency) {
+        let unit = if inherited {
+            flatten_unit(&hierarchy, &source.unit)
+        } else {
+            source.unit.clone()
+        };
+        let mut functions = extract_functions(&unit, &source.path);
+        functions.retain(|f| f.visibility == "external" || f.visibility == "public");
+        all_functions.extend(functions);
+
+        let file = source.path.display().to_string();
+        all_contracts.extend(
+            unit.contracts
+                .iter()
+                .filter_map(|c| ContractOutput::from_contract(c, &file)),
+        );
+    }
+
+    // artifacts of contracts found in the sources are only used to check them
+    let artifacts = load_artifacts(path);
+    let all_rows: Vec<FunctionOutput> = if artifacts.is_empty() {
+        Vec::new()
+    } else {
+        sources
+            .files
+            .iter()
+            .flat_map(|f| extract_functions(&f.unit, &f.path))
+            .collect()
+    };
+    let known = all_rows
+        .iter()
+        .map(|r| (r.kind, r.selector.as_str()))
+        .collect();
+
+    for artifact in artifacts {
+        if !check_artifact(&artifact, &sources, &hierarchy, &known) {
+            all_functions.extend(artifact.rows);
+        }
+    }
+
+    (all_functions, all_contracts)
+}
+
+// warns about every selector the artifact and the contract's source disagree on; false when
+// the contract is not in the file it was compiled from. `known` holds every selector and
+// topic declared anywhere in the sources
+fn check_artifact(
+    artifact: &Artifact,
+    sources: &Sources,
+    hierarchy: &Hierarchy,
+    known: &HashSet<(Kind, &str)>,
+) -> bool {
+    let mut files = sources.files.iter().filter(|f| artifact.is_from(&f.path));
+    let Some((source, contract)) = files.find_map(|f| {
+        f.unit
+            .contracts
+            .iter()
+            .find(|c| c.name == artifact.contract)
+            .map(|c| (f, c))
+    }) else {
+        return false;
+    };
+
+    // artifacts list everything a contract inherits
+    let flat = hierarchy
+        .flatten(contract)
+        .unwrap_or_else(|_| contract.clone());
+    let unit = SourceUnit {
+        contracts: vec![flat],
+        ..SourceUnit::default()
+    };
+    let mut rows = extract_functions(&unit, &source.path);
+    rows.retain(|f| f.visibility == "external" || f.visibility == "public");
+
+    let differences = artifact.compare(&rows, known);
+    for row in differences.missing {
+        eprintln!(
+            "warning: {} {} {} of {} is in {} but was not found in {}",
+            row.kind.as_str(),
+            row.selector,
+            row.signature,
+            artifact.contract,
+            row.file,
+            source.path.display()
+        );
+    }
+    for row in differences.unexpected {
+        eprintln!(
+            "warning: {} {} {} of {} is in {} but not in the compiled {}",
+            row.kind.as_str(),
+            row.selector,
+            row.signature,
+            artifact.contract,
+            row.location(),
+            artifact.path.display()
+        );
+    }
+
+    true
+}
+
+// every declaration that could not be parsed, then how many were skipped in each file
+fn report_diagnostics(sources: &Sources, strict: bool) {
+    let level = if strict { "error" } else { "warning" };
+
+    for source in &sources.files {
+        let path = source.path.display().to_string();
+        for diagnostic in &source.diagnostics {
+            eprintln!(
+                "{}: {}\n{}\n",
+                level,
+                diagnostic,
+                code_frame(&source.source, &path, diagnostic.span())
+            );
+        }
+    }
+
+    let mut skipped = 0;
+    for source in sources.files.iter().filter(|f| !f.diagnostics.is_empty()) {
+        let count = source.diagnostics.len();
+        skipped += count;
+        eprintln!(
+            "{}: skipped {} declaration{} in {}",
+            level,
+            count,
+            if count == 1 { "" } else { "s" },
+            source.path.display()
+        );
+    }
+
+    if strict && skipped > 0 {
+        std::process::exit(1);
+    }
+}
+
+// compiled artifacts under the path; json files that are no artifacts are skipped
+fn load_artifacts(path: &Path) -> Vec<Artifact> {
+    let files = if path.is_dir() {
+        WalkDir::new(path)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .map(|e| e.into_path())
+            .filter(|p| is_artifact_file(p))
+            .collect()
+    } else if path.extension().is_some_and(|ext| ext == "json") {
+        vec![path.to_path_buf()]
+    } else {
+        Vec::new()
+    };
+
+    files
+        .iter()
+        .filter_map(|file| match Artifact::load(file) {
+            Ok(artifact) => artifact,
+            Err(e) => {
+                eprintln!("warning: failed to read {}: {}", file.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+// json files in foundry's out/ or hardhat's artifacts/, except hardhat's debug files
+fn is_artifact_file(path: &Path) -> bool {
+    let in_output_dir = path
+        .components()
+        .any(|c| c.as_os_str() == "out" || c.as_os_str() == "artifacts");
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+
+    in_output_dir && name.ends_with(".json") && !name.ends_with(".dbg.json")
+}
+
+// solidity and vyper sources
+fn find_source_files(dir: &Path) -> Vec<PathBuf> {
     WalkDir::new(dir)
         .into_iter()
         .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().is_some_and(|ext| ext == "sol"))
+        // foundry's out/Token.sol/ is a directory
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            e.path()
+                .extension()
+                .is_some_and(|ext| ext == "sol" || ext == "vy" || ext == "vyi")
+        })
         .map(|e| e.path().to_path_buf())
         .collect()
 }
 
-fn extract_functions_from_file(
-    path: &Path,
-) -> Result<Vec<FunctionOutput>, Box<dyn std::error::Error>> {
-    let source = std::fs::read_to_string(path)?;
+// replaces every contract with its full inherited interface
+fn flatten_unit(hierarchy: &Hierarchy, unit: &SourceUnit) -> SourceUnit {
+    let contracts = unit
+        .contracts
+        .iter()
+        .map(|contract| {
+            if let Ok(linearization) = hierarchy.linearize(contract) {
+                for base in linearization.iter().filter(|b| hierarchy.get(b).is_none()) {
+                    eprintln!(
+                        "warning: base contract {} of {} was not found",
+                        base, contract.name
+                    );
+                }
+            }
 
-    let mut lexer = Lexer::new(&source);
-    let tokens = lexer.tokenize();
+            let mut flat = hierarchy.flatten(contract).unwrap_or_else(|e| {
+                eprintln!("warning: {}", e);
+                contract.clone()
+            });
 
-    let mut parser = SolidityParser::new(tokens);
-    let (functions, variables) = parser.parse_all_symbols();
+            // inherited members were declared in another contract, possibly another file
+            for func in flat.functions.iter_mut() {
+                if !contract.functions.contains(func) {
+                    func.span = Span::default();
+                }
+            }
+            for var in flat.variables.iter_mut() {
+                if !contract.variables.contains(var) {
+                    var.span = Span::default();
+                }
+            }
+            for event in flat.events.iter_mut() {
+                if !contract.events.contains(event) {
+                    event.span = Span::default();
+                }
+            }
+            for error in flat.errors.iter_mut() {
+                if !contract.errors.contains(error) {
+                    error.span = Span::default();
+                }
+            }
 
-    let mut functions_output: Vec<FunctionOutput> = functions
-        .iter()
-        .map(|f| FunctionOutput::from_function(f))
+            flat
+        })
         .collect();
 
-    let variables_output: Vec<FunctionOutput> = variables
-        .iter()
-        .filter_map(|v| FunctionOutput::from_variable(v))
-        .collect();
+    SourceUnit {
+        contracts,
+        ..unit.clone()
+    }
+}
+
+fn extract_functions(unit: &SourceUnit, path: &Path) -> Vec<FunctionOutput> {
+    let file = path.display().to_string();
+
+    let mut output = Vec::new();
+
+    for contract in &unit.contracts {
+        let name = Some(contract.name.as_str());
+
+        if contract.kind == ContractKind::Library {
+            output.extend(
+                contract
+                    .functions
+                    .iter()
+                    .map(|f| FunctionOutput::from_library_function(f, name, &file)),
+            );
+        } else {
+            output.extend(
+                contract
+                    .functions
+                    .iter()
+                    .map(|f| FunctionOutput::from_function(f, name, &file)),
+            );
+        }
+        output.extend(
+            contract
+                .variables
+                .iter()
+                .filter_map(|v| FunctionOutput::from_variable(v, name, &file)),
+        );
+        output.extend(
+            contract
+                .events
+                .iter()
+                .filter_map(|e| FunctionOutput::from_event(e, name, &file)),
+        );
+        output.extend(
+            contract
+                .errors
+                .iter()
+                .map(|e| FunctionOutput::from_error(e, name, &file)),
+        );
+    }
 
-    functions_output.extend(variables_output);
+    // file-level declarations; free functions are never part of an ABI
+    output.extend(
+        unit.events
+            .iter()
+            .filter_map(|e| FunctionOutput::from_event(e, None, &file)),
+    );
+    output.extend(
+        unit.errors
+            .iter()
+            .map(|e| FunctionOutput::from_error(e, None, &file)),
+    );
 
-    Ok(functions_output)
+    output
 }
@@ -1,18 +1,55 @@
-use crate::ast::{Function, Variable, Visibility};
-use crate::selector::{compute_selector, format_selector};
+use crate::ast::{
+    ContractDefinition, ContractKind, Error, Event, Function, Mutability, SpecialKind, Variable,
+    Visibility,
+};
+use crate::db::Entry;
+use crate::selector::{
+    compute_interface_id, compute_selector, compute_topic, format_selector, format_topic,
+};
+use crate::span::Span;
 use serde::Serialize;
 
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Function,
+    Event,
+    Error,
+}
+
+impl Kind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Function => "function",
+            Kind::Event => "event",
+            Kind::Error => "error",
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct FunctionOutput {
     pub selector: String,
+    pub kind: Kind,
     pub signature: String,
+    pub contract: Option<String>,
+    pub file: String,
+    pub line: Option<usize>, // where it is declared, None for inherited members
     #[serde(skip_serializing)]
     pub visibility: String,
 }
 
 impl FunctionOutput {
-    pub fn from_function(func: &Function) -> Self {
-        let signature = func.signature();
+    pub fn from_function(func: &Function, contract: Option<&str>, file: &str) -> Self {
+        Self::function(func, func.signature(), contract, file)
+    }
+
+    // functions of a library, see `Function::library_signature`
+    pub fn from_library_function(func: &Function, contract: Option<&str>, file: &str) -> Self {
+        Self::function(func, func.library_signature(), contract, file)
+    }
+
+    fn function(func: &Function, signature: String, contract: Option<&str>, file: &str) -> Self {
         let selector = compute_selector(&signature);
         let visibility = match func.visibility {
             Visibility::External => "external",
@@ -24,12 +61,16 @@ impl FunctionOutput {
 
         FunctionOutput {
             selector: format_selector(&selector),
+            kind: Kind::Function,
             signature,
+            contract: contract.map(str::to_string),
+            file: file.to_string(),
+            line: line(func.span),
             visibility,
         }
     }
 
-    pub fn from_variable(var: &Variable) -> Option<Self> {
+    pub fn from_variable(var: &Variable, contract: Option<&str>, file: &str) -> Option<Self> {
         if var.visibility != Visibility::Public {
             return None;
         }
@@ -39,10 +80,167 @@ impl FunctionOutput {
 
         Some(FunctionOutput {
             selector: format_selector(&selector),
+            kind: Kind::Function,
             signature,
+            contract: contract.map(str::to_string),
+            file: file.to_string(),
+            line: line(var.span),
             visibility: "public".to_string(),
         })
     }
+
+    // anonymous events have no topic0 to look them up by
+    pub fn from_event(event: &Event, contract: Option<&str>, file: &str) -> Option<Self> {
+        if event.anonymous {
+            return None;
+        }
+
+        let signature = event.signature();
+        let topic = compute_topic(&signature);
+
+        Some(FunctionOutput {
+            selector: format_topic(&topic),
+            kind: Kind::Event,
+            signature,
+            contract: contract.map(str::to_string),
+            file: file.to_string(),
+            line: line(event.span),
+            visibility: "public".to_string(),
+        })
+    }
+
+    pub fn from_error(error: &Error, contract: Option<&str>, file: &str) -> Self {
+        let signature = error.signature();
+        let selector = compute_selector(&signature);
+
+        FunctionOutput {
+            selector: format_selector(&selector),
+            kind: Kind::Error,
+            signature,
+            contract: contract.map(str::to_string),
+            file: file.to_string(),
+            line: line(error.span),
+            visibility: "public".to_string(),
+        }
+    }
+
+    // file:line, or just the file when the line is unknown
+    pub fn location(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}:{}", self.file, line),
+            None => self.file.clone(),
+        }
+    }
+}
+
+// what a contract does without a selector: how it is deployed, and whether calls that match
+// no selector or carry no data are accepted
+#[derive(Serialize)]
+pub struct ContractOutput {
+    pub contract: String,
+    pub constructor: Option<Vec<String>>, // argument types, None without an explicit constructor
+    pub fallback: bool,
+    pub receive: bool, // plain transfers, through receive or a payable fallback
+    pub file: String,
+    pub line: Option<usize>,
+}
+
+impl ContractOutput {
+    // interfaces and libraries are never deployed with arguments nor called without a selector
+    pub fn from_contract(contract: &ContractDefinition, file: &str) -> Option<Self> {
+        if matches!(
+            contract.kind,
+            ContractKind::Interface | ContractKind::Library
+        ) {
+            return None;
+        }
+
+        let constructor = contract
+            .special_function(SpecialKind::Constructor)
+            .map(|c| {
+                c.parameters
+                    .iter()
+                    .map(|p| p.type_name.canonical())
+                    .collect()
+            });
+
+        let fallback = contract.special_function(SpecialKind::Fallback);
+        let receive = contract.special_function(SpecialKind::Receive).is_some()
+            || fallback.is_some_and(|f| f.mutability == Some(Mutability::Payable));
+
+        Some(ContractOutput {
+            contract: contract.name.clone(),
+            constructor,
+            fallback: fallback.is_some(),
+            receive,
+            file: file.to_string(),
+            line: line(contract.span),
+        })
+    }
+}
+
+// type(I).interfaceId of an interface
+#[derive(Serialize)]
+pub struct InterfaceOutput {
+    pub interface_id: String,
+    pub interface: String,
+    pub file: String,
+    pub line: Option<usize>,
+}
+
+impl InterfaceOutput {
+    // only the functions the interface declares itself count, not those of its bases
+    pub fn from_contract(contract: &ContractDefinition, file: &str) -> Option<Self> {
+        if contract.kind != ContractKind::Interface {
+            return None;
+        }
+
+        let signatures: Vec<String> = contract.functions.iter().map(|f| f.signature()).collect();
+        let id = compute_interface_id(signatures.iter().map(String::as_str));
+
+        Some(InterfaceOutput {
+            interface_id: format_selector(&id),
+            interface: contract.name.clone(),
+            file: file.to_string(),
+            line: line(contract.span),
+        })
+    }
+}
+
+// every known signature for one selector or topic
+#[derive(Serialize)]
+pub struct LookupOutput {
+    pub selector: String,
+    pub collision: bool, // more than one signature shares the selector
+    pub matches: Vec<FunctionOutput>,
+}
+
+impl LookupOutput {
+    // the same signature declared in several contracts is no collision
+    pub fn new(selector: String, matches: Vec<FunctionOutput>) -> Self {
+        let mut signatures: Vec<&str> = matches.iter().map(|m| m.signature.as_str()).collect();
+        signatures.sort_unstable();
+        signatures.dedup();
+
+        LookupOutput {
+            selector,
+            collision: signatures.len() > 1,
+            matches,
+        }
+    }
+}
+
+// a selector found in a contract's dispatcher
+#[derive(Serialize)]
+pub struct DispatchOutput {
+    pub selector: String,
+    pub entry: String,           // offset of the code it dispatches to
+    pub signatures: Vec<String>, // known ones, when resolved against a database
+}
+
+// spans are empty when parsed without positions
+fn line(span: Span) -> Option<usize> {
+    (span.line > 0).then_some(span.line)
 }
 
 // tabbed output, default
@@ -52,23 +250,204 @@ pub fn output_tsv(functions: &[FunctionOutput]) {
         .map(|f| f.signature.len())
         .max()
         .unwrap_or(0);
+    let contract_len = functions
+        .iter()
+        .map(|f| f.contract.as_deref().unwrap_or("-").len())
+        .max()
+        .unwrap_or(0)
+        .max("contract".len());
+    // event topics are much wider than 4-byte selectors
+    let selector_len = functions
+        .iter()
+        .map(|f| f.selector.len())
+        .max()
+        .unwrap_or(0)
+        .max(15);
     // header
-    println!("{:<15} {:<len$}", "selector", "signature", len = max_len);
+    println!(
+        "{:<sel$} {:<10} {:<len$} {:<con$} file",
+        "selector",
+        "kind",
+        "signature",
+        "contract",
+        sel = selector_len,
+        len = max_len,
+        con = contract_len
+    );
 
     // rows
     for func in functions {
         println!(
-            "{:<15} {:<len$}",
+            "{:<sel$} {:<10} {:<len$} {:<con$} {}",
             func.selector,
+            func.kind.as_str(),
             func.signature,
-            len = max_len
+            func.contract.as_deref().unwrap_or("-"),
+            func.location(),
+            sel = selector_len,
+            len = max_len,
+            con = contract_len
         );
     }
 }
 
+// contracts table printed after the selectors
+pub fn output_contracts_tsv(contracts: &[ContractOutput]) {
+    let constructors: Vec<String> = contracts
+        .iter()
+        .map(|c| match &c.constructor {
+            Some(args) => format!("({})", args.join(",")),
+            None => "-".to_string(),
+        })
+        .collect();
+    let contract_len = contracts
+        .iter()
+        .map(|c| c.contract.len())
+        .max()
+        .unwrap_or(0)
+        .max("contract".len());
+    let constructor_len = constructors
+        .iter()
+        .map(|c| c.len())
+        .max()
+        .unwrap_or(0)
+        .max("constructor".len());
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+
+    println!();
+    println!(
+        "{:<con$} {:<ctor$} {:<8} {:<7} file",
+        "contract",
+        "constructor",
+        "fallback",
+        "receive",
+        con = contract_len,
+        ctor = constructor_len
+    );
+
+    for (contract, constructor) in contracts.iter().zip(&constructors) {
+        let location = match contract.line {
+            Some(line) => format!("{}:{}", contract.file, line),
+            None => contract.file.clone(),
+        };
+        println!(
+            "{:<con$} {:<ctor$} {:<8} {:<7} {}",
+            contract.contract,
+            constructor,
+            yes_no(contract.fallback),
+            yes_no(contract.receive),
+            location,
+            con = contract_len,
+            ctor = constructor_len
+        );
+    }
+}
+
+pub fn output_interfaces_tsv(interfaces: &[InterfaceOutput]) {
+    let name_len = interfaces
+        .iter()
+        .map(|i| i.interface.len())
+        .max()
+        .unwrap_or(0)
+        .max("interface".len());
+
+    println!(
+        "{:<12} {:<len$} file",
+        "interface_id",
+        "interface",
+        len = name_len
+    );
+    for interface in interfaces {
+        let location = match interface.line {
+            Some(line) => format!("{}:{}", interface.file, line),
+            None => interface.file.clone(),
+        };
+        println!(
+            "{:<12} {:<len$} {}",
+            interface.interface_id,
+            interface.interface,
+            location,
+            len = name_len
+        );
+    }
+}
+
+// database entries, with when they were first added
+pub fn output_entries_tsv(entries: &[Entry]) {
+    let selector_len = entries
+        .iter()
+        .map(|e| e.selector.len())
+        .max()
+        .unwrap_or(0)
+        .max(15);
+    let signature_len = entries
+        .iter()
+        .map(|e| e.signature.len())
+        .max()
+        .unwrap_or(0)
+        .max("signature".len());
+    let contract_len = entries
+        .iter()
+        .map(|e| e.contract.as_deref().unwrap_or("-").len())
+        .max()
+        .unwrap_or(0)
+        .max("contract".len());
+
+    println!(
+        "{:<sel$} {:<10} {:<len$} {:<con$} {:<19} file",
+        "selector",
+        "kind",
+        "signature",
+        "contract",
+        "first_seen",
+        sel = selector_len,
+        len = signature_len,
+        con = contract_len
+    );
+    for entry in entries {
+        println!(
+            "{:<sel$} {:<10} {:<len$} {:<con$} {:<19} {}",
+            entry.selector,
+            entry.kind.as_str(),
+            entry.signature,
+            entry.contract.as_deref().unwrap_or("-"),
+            entry.first_seen,
+            entry.location(),
+            sel = selector_len,
+            len = signature_len,
+            con = contract_len
+        );
+    }
+}
+
+pub fn output_dispatch_tsv(entries: &[DispatchOutput]) {
+    println!("{:<10} {:<8} signature", "selector", "entry");
+    for entry in entries {
+        let signatures = if entry.signatures.is_empty() {
+            "-".to_string()
+        } else {
+            entry.signatures.join(", ")
+        };
+        println!("{:<10} {:<8} {}", entry.selector, entry.entry, signatures);
+    }
+}
+
 // json output
-pub fn output_json(functions: &[FunctionOutput]) -> Result<(), serde_json::Error> {
-    let json = serde_json::to_string_pretty(functions)?;
+pub fn output_json<T: Serialize + ?Sized>(value: &T) -> Result<(), serde_json::Error> {
+    let json = serde_json::to_string_pretty(value)?;
+    println!("{}", json);
+    Ok(())
+}
+
+// json output with the contracts table alongside the selectors
+pub fn output_json_with_contracts(
+    functions: &[FunctionOutput],
+    contracts: &[ContractOutput],
+) -> Result<(), serde_json::Error> {
+    let json = serde_json::to_string_pretty(&serde_json::json!({
+        "selectors": functions,
+        "contracts": contracts,
+    }))?;
     println!("{}", json);
     Ok(())
 }
@@ -1,18 +1,162 @@
+use std::fmt;
+
 use crate::{
-    ast::{DataLocation, Function, Mutability, Parameter, Type, Variable, Visibility},
+    ast::{
+        ContractDefinition, ContractKind, DataLocation, Enum, Error, Event, EventParameter,
+        Function, FunctionType, Import, Mutability, Parameter, SourceUnit, SpecialFunction,
+        SpecialKind, Struct, Type, UserValueType, Variable, Visibility, is_valid_elementary,
+    },
     lexer::Token,
+    span::Span,
+    symbols::SymbolTable,
+    version::VersionReq,
 };
 
+// why a declaration could not be parsed, at the token where parsing stopped
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    UnexpectedToken {
+        expected: Token,
+        found: Token,
+        span: Span,
+    },
+    // a kind of token rather than a specific one: an identifier, a type, an import path
+    Expected {
+        what: &'static str,
+        found: Token,
+        span: Span,
+    },
+    InvalidArray {
+        found: Token,
+        span: Span,
+    },
+    // uint7, bytes33, fixed8x81
+    InvalidType {
+        name: String,
+        span: Span,
+    },
+    // a type where only a (possibly qualified) name is allowed, as in `is uint256`
+    InvalidName {
+        what: &'static str,
+        found: Type,
+        span: Span,
+    },
+    // from front-ends that do not work on solidity tokens, such as vyper's
+    Syntax {
+        message: String,
+        span: Span,
+    },
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::Expected { span, .. }
+            | ParseError::InvalidArray { span, .. }
+            | ParseError::InvalidType { span, .. }
+            | ParseError::InvalidName { span, .. }
+            | ParseError::Syntax { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedToken {
+                expected, found, ..
+            } => write!(f, "expected {:?}, found {:?}", expected, found),
+            ParseError::Expected { what, found, .. } => {
+                write!(f, "expected {}, found {:?}", what, found)
+            }
+            ParseError::InvalidArray { found, .. } => {
+                write!(f, "invalid array length, found {:?}", found)
+            }
+            ParseError::InvalidType { name, .. } => write!(f, "invalid type {}", name),
+            ParseError::InvalidName { what, found, .. } => {
+                write!(f, "invalid {}: {}", what, found.canonical())
+            }
+            ParseError::Syntax { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 pub struct Parser {
     tokens: Vec<Token>,
+    spans: Vec<Span>, // one per token, empty when parsing without positions
     position: usize,
+    symbols: SymbolTable,
+    solidity: Option<VersionReq>,
+    diagnostics: Vec<ParseError>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
         Parser {
             tokens,
+            spans: Vec::new(),
             position: 0,
+            symbols: SymbolTable::new(),
+            solidity: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn with_spans(tokens: Vec<(Token, Span)>) -> Self {
+        let (tokens, spans) = tokens.into_iter().unzip();
+        Parser {
+            spans,
+            ..Parser::new(tokens)
+        }
+    }
+
+    // declarations seen by `parse_all_symbols`, for resolving types used in other files
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    // functions only defaulted to public before 0.5.0, later compilers require a visibility.
+    // without a pragma we assume the code may be that old rather than drop its functions
+    fn default_function_visibility(&self) -> Visibility {
+        match &self.solidity {
+            Some(req) if !req.allows_pre_050() => Visibility::Internal,
+            _ => Visibility::Public,
+        }
+    }
+
+    // one error for every declaration that was skipped
+    pub fn diagnostics(&self) -> &[ParseError] {
+        &self.diagnostics
+    }
+
+    fn span(&self) -> Span {
+        self.spans.get(self.position).copied().unwrap_or_default()
+    }
+
+    fn expected(&self, what: &'static str) -> ParseError {
+        ParseError::Expected {
+            what,
+            found: self.current().clone(),
+            span: self.span(),
+        }
+    }
+
+    fn invalid_array(&self) -> ParseError {
+        ParseError::InvalidArray {
+            found: self.current().clone(),
+            span: self.span(),
+        }
+    }
+
+    // a possibly qualified name written where the grammar allows a type, as in `is Base`
+    fn parse_name(&mut self, what: &'static str) -> Result<String, ParseError> {
+        let span = self.span();
+        match self.parse_type()? {
+            Type::UserDefined(name) => Ok(name),
+            found => Err(ParseError::InvalidName { what, found, span }),
         }
     }
 
@@ -20,22 +164,32 @@ impl Parser {
         self.tokens.get(self.position).unwrap_or(&Token::Eof)
     }
 
+    fn peek(&self) -> &Token {
+        self.peek_at(1)
+    }
+
+    fn peek_at(&self, offset: usize) -> &Token {
+        self.tokens
+            .get(self.position + offset)
+            .unwrap_or(&Token::Eof)
+    }
+
     fn advance(&mut self) {
         if self.position < self.tokens.len() {
             self.position += 1;
         }
     }
 
-    fn expect(&mut self, expected: Token) -> Result<(), String> {
+    fn expect(&mut self, expected: Token) -> Result<(), ParseError> {
         if self.current() == &expected {
             self.advance();
             Ok(())
         } else {
-            Err(format!(
-                "unexpected token: {:?}, expected: {:?}",
-                self.current(),
-                expected
-            ))
+            Err(ParseError::UnexpectedToken {
+                expected,
+                found: self.current().clone(),
+                span: self.span(),
+            })
         }
     }
 
@@ -63,77 +217,346 @@ impl Parser {
         }
     }
 
-    pub fn parse_variable(&mut self) -> Result<Variable, String> {
-        let type_name = self.parse_type()?;
-        let visibility = self.parse_visibility()?;
+    fn advance_parenthesized(&mut self) {
+        if self.current() != &Token::LeftParen {
+            return;
+        }
 
-        let name = match self.current() {
-            Token::Identifier(n) => {
-                let name = n.clone();
-                self.advance();
-                name
+        let mut depth = 0;
+        while self.current() != &Token::Eof {
+            match self.current() {
+                Token::LeftParen => {
+                    depth += 1;
+                    self.advance();
+                }
+                Token::RightParen => {
+                    depth -= 1;
+                    self.advance();
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => self.advance(),
             }
-            _ => return Err(format!("expected identifier, found: {:?}", self.current())),
-        };
+        }
+    }
 
-        if self.current() == &Token::Semicolon {
-            self.advance();
+    pub fn parse_variable(&mut self) -> Result<Variable, ParseError> {
+        let type_name = self.parse_type()?;
+
+        // state variables are internal unless declared otherwise, in every version
+        let mut visibility = Visibility::Internal;
+        let mut overrides = None;
+        loop {
+            match self.current() {
+                Token::External | Token::Public | Token::Internal | Token::Private => {
+                    visibility = self.parse_visibility()?;
+                }
+                Token::Override => overrides = Some(self.parse_override()?),
+                Token::Identifier(s)
+                    if matches!(s.as_str(), "constant" | "immutable" | "transient") =>
+                {
+                    self.advance();
+                }
+                _ => break,
+            }
         }
 
-        let returns = Parameter {
-            type_name: type_name.clone(),
-            name: None,
-            data_location: None,
-        };
+        let span = self.span();
+        let name = self.parse_identifier()?;
+
+        // initializer, may contain calls with their own parentheses
+        if self.current() == &Token::Unknown('=') {
+            while !matches!(self.current(), Token::Semicolon | Token::Eof) {
+                if self.current() == &Token::LeftParen {
+                    self.advance_parenthesized();
+                } else {
+                    self.advance();
+                }
+            }
+        }
+        self.expect(Token::Semicolon)?;
 
         Ok(Variable {
             type_name,
             name,
             visibility,
-            returns,
+            overrides,
+            span,
         })
     }
 
-    pub fn parse_function(&mut self) -> Result<Function, String> {
+    pub fn parse_function(&mut self) -> Result<Function, ParseError> {
         self.expect(Token::Function)?;
 
-        let name = match self.current() {
-            Token::Identifier(n) => {
-                let name = n.clone();
-                self.advance();
-                name
-            }
-            _ => return Err(format!("expected identifier, found: {:?}", self.current())),
-        };
+        let span = self.span();
+        let name = self.parse_identifier()?;
+
+        self.expect(Token::LeftParen)?;
+        let parameters = self.parse_parameter_list()?;
+        self.expect(Token::RightParen)?;
+
+        let specifiers = self.parse_function_specifiers()?;
+        self.skip_function_body();
+
+        Ok(Function {
+            name,
+            parameters,
+            visibility: specifiers
+                .visibility
+                .unwrap_or_else(|| self.default_function_visibility()),
+            mutability: specifiers.mutability,
+            is_virtual: specifiers.is_virtual,
+            overrides: specifiers.overrides,
+            modifiers: specifiers.modifiers,
+            returns: specifiers.returns,
+            span,
+        })
+    }
+
+    // constructor(..), fallback(..), receive() and the legacy unnamed function(..); the
+    // keyword or identifier introducing it has been checked by the caller
+    pub fn parse_special_function(
+        &mut self,
+        kind: SpecialKind,
+    ) -> Result<SpecialFunction, ParseError> {
+        let span = self.span();
+        self.advance();
 
         self.expect(Token::LeftParen)?;
         let parameters = self.parse_parameter_list()?;
         self.expect(Token::RightParen)?;
 
-        let visibility = self.parse_visibility()?;
-        let mutability = self.parse_state_mutability();
-        let returns = if self.current() == &Token::Returns {
+        let specifiers = self.parse_function_specifiers()?;
+        self.skip_function_body();
+
+        Ok(SpecialFunction {
+            kind,
+            parameters,
+            mutability: specifiers.mutability,
+            span,
+        })
+    }
+
+    // specifiers may come in any order
+    fn parse_function_specifiers(&mut self) -> Result<FunctionSpecifiers, ParseError> {
+        let mut specifiers = FunctionSpecifiers::default();
+        loop {
+            match self.current() {
+                Token::External | Token::Public | Token::Internal | Token::Private => {
+                    specifiers.visibility = Some(self.parse_visibility()?);
+                }
+                Token::Pure | Token::View | Token::Payable => {
+                    specifiers.mutability = self.parse_state_mutability();
+                }
+                // pre-0.5 spelling of view
+                Token::Identifier(s) if s == "constant" => {
+                    self.advance();
+                    specifiers.mutability = Some(Mutability::View);
+                }
+                Token::Virtual => {
+                    specifiers.is_virtual = true;
+                    self.advance();
+                }
+                Token::Override => specifiers.overrides = Some(self.parse_override()?),
+                // modifiers, or base constructor calls on a constructor
+                Token::Identifier(_) => {
+                    specifiers.modifiers.push(self.parse_modifier_invocation()?);
+                }
+                Token::Returns => {
+                    self.advance();
+                    self.expect(Token::LeftParen)?;
+                    specifiers.returns = Some(self.parse_parameter_list()?);
+                    self.expect(Token::RightParen)?;
+                }
+                _ => break,
+            }
+        }
+
+        Ok(specifiers)
+    }
+
+    fn skip_function_body(&mut self) {
+        if self.current() == &Token::Semicolon {
             self.advance();
-            self.expect(Token::LeftParen)?;
-            let ret = self.parse_parameter_list()?;
-            self.expect(Token::RightParen)?;
-            Some(ret)
         } else {
-            None
-        };
+            self.advance_braced_block();
+        }
+    }
 
-        self.advance_braced_block();
+    // onlyOwner, onlyRole(ADMIN) or Base.modifier(x); arguments are skipped
+    fn parse_modifier_invocation(&mut self) -> Result<String, ParseError> {
+        let name = self.parse_name("modifier")?;
+        self.advance_parenthesized();
 
-        Ok(Function {
+        Ok(name)
+    }
+
+    // override or override(A, B)
+    fn parse_override(&mut self) -> Result<Vec<String>, ParseError> {
+        self.expect(Token::Override)?;
+
+        let mut bases = Vec::new();
+        if self.current() != &Token::LeftParen {
+            return Ok(bases);
+        }
+
+        self.advance();
+        while self.current() != &Token::RightParen {
+            bases.push(self.parse_name("override base")?);
+
+            if self.current() == &Token::Comma {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.expect(Token::RightParen)?;
+
+        Ok(bases)
+    }
+
+    pub fn parse_event(&mut self) -> Result<Event, ParseError> {
+        self.expect(Token::Event)?;
+
+        let span = self.span();
+        let name = self.parse_identifier()?;
+
+        self.expect(Token::LeftParen)?;
+
+        let mut parameters = Vec::new();
+        while self.current() != &Token::RightParen {
+            let type_name = self.parse_type()?;
+
+            let indexed = self.current() == &Token::Indexed;
+            if indexed {
+                self.advance();
+            }
+
+            let param_name = match self.current() {
+                Token::Identifier(n) => {
+                    let name = n.clone();
+                    self.advance();
+                    Some(name)
+                }
+                _ => None,
+            };
+
+            parameters.push(EventParameter {
+                type_name,
+                name: param_name,
+                indexed,
+            });
+
+            if self.current() == &Token::Comma {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        self.expect(Token::RightParen)?;
+
+        let anonymous = self.current() == &Token::Anonymous;
+        if anonymous {
+            self.advance();
+        }
+
+        self.expect(Token::Semicolon)?;
+
+        Ok(Event {
             name,
             parameters,
-            visibility,
-            mutability,
-            returns,
+            anonymous,
+            span,
+        })
+    }
+
+    pub fn parse_error(&mut self) -> Result<Error, ParseError> {
+        // not a keyword, variables and parameters may be named `error`
+        match self.current() {
+            Token::Identifier(kw) if kw == "error" => self.advance(),
+            _ => return Err(self.expected("error")),
+        }
+
+        let span = self.span();
+        let name = self.parse_identifier()?;
+
+        self.expect(Token::LeftParen)?;
+        let parameters = self.parse_parameter_list()?;
+        self.expect(Token::RightParen)?;
+        self.expect(Token::Semicolon)?;
+
+        Ok(Error {
+            name,
+            parameters,
+            span,
         })
     }
 
-    fn parse_parameter_list(&mut self) -> Result<Vec<Parameter>, String> {
+    pub fn parse_struct(&mut self) -> Result<Struct, ParseError> {
+        self.expect(Token::Struct)?;
+
+        let name = self.parse_identifier()?;
+
+        self.expect(Token::LeftBrace)?;
+
+        let mut members = Vec::new();
+        while self.current() != &Token::RightBrace {
+            let type_name = self.parse_type()?;
+            let member_name = self.parse_identifier()?;
+            self.expect(Token::Semicolon)?;
+
+            members.push(Parameter {
+                type_name,
+                name: Some(member_name),
+                data_location: None,
+            });
+        }
+
+        self.expect(Token::RightBrace)?;
+
+        Ok(Struct { name, members })
+    }
+
+    pub fn parse_enum(&mut self) -> Result<Enum, ParseError> {
+        self.expect(Token::Enum)?;
+
+        let name = self.parse_identifier()?;
+
+        self.expect(Token::LeftBrace)?;
+
+        let mut values = Vec::new();
+        while let Token::Identifier(v) = self.current() {
+            values.push(v.clone());
+            self.advance();
+
+            if self.current() == &Token::Comma {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        self.expect(Token::RightBrace)?;
+
+        Ok(Enum { name, values })
+    }
+
+    // type Price is uint128;
+    pub fn parse_value_type(&mut self) -> Result<UserValueType, ParseError> {
+        self.expect(Token::TypeDef)?;
+
+        let name = self.parse_identifier()?;
+
+        self.expect(Token::Is)?;
+        let underlying = self.parse_type()?;
+        self.expect(Token::Semicolon)?;
+
+        Ok(UserValueType { name, underlying })
+    }
+
+    fn parse_parameter_list(&mut self) -> Result<Vec<Parameter>, ParseError> {
         let mut params = Vec::new();
 
         if self.current() == &Token::RightParen {
@@ -154,7 +577,7 @@ impl Parser {
         Ok(params)
     }
 
-    fn parse_parameter(&mut self) -> Result<Parameter, String> {
+    fn parse_parameter(&mut self) -> Result<Parameter, ParseError> {
         let type_name = self.parse_type()?;
 
         let data_location = match self.current() {
@@ -189,16 +612,18 @@ impl Parser {
         })
     }
 
-    fn parse_type(&mut self) -> Result<Type, String> {
+    fn parse_type(&mut self) -> Result<Type, ParseError> {
         if self.current() == &Token::Mapping {
             self.advance();
             self.expect(Token::LeftParen)?;
 
             let key_type = self.parse_type()?;
+            self.skip_mapping_name();
 
             self.expect(Token::Arrow)?;
 
             let value_type = self.parse_type()?; // recursive, value could be another mapping
+            self.skip_mapping_name();
 
             self.expect(Token::RightParen)?;
 
@@ -206,75 +631,148 @@ impl Parser {
         }
 
         let base_type = match self.current() {
-            Token::Type(t) => {
-                let type_name = t.clone();
-                self.advance();
-                Type::Elementary(type_name)
-            }
-            Token::Identifier(t) => {
-                let type_name = t.clone();
-                self.advance();
-                Type::Elementary(type_name)
-            }
-            _ => return Err("expected type".to_string()),
+            Token::Function => self.parse_function_type()?,
+            _ => self.parse_base_type()?,
         };
 
-        // array?
-        if self.current() == &Token::LeftBracket {
+        // arrays, possibly nested: uint256[2][] is a dynamic array of uint256[2]
+        let mut t = base_type;
+        while self.current() == &Token::LeftBracket {
             self.advance();
 
             if let Token::Number(size_str) = self.current() {
-                let size: usize = size_str
-                    .parse()
-                    .map_err(|_| "invalid array size".to_string())?;
+                let size: usize = size_str.parse().map_err(|_| self.invalid_array())?;
                 self.advance();
                 self.expect(Token::RightBracket)?;
-                Ok(Type::FixedArray(Box::new(base_type), size))
+                t = Type::FixedArray(Box::new(t), size);
             } else if self.current() == &Token::RightBracket {
                 // dynamic array
                 self.advance();
-                Ok(Type::Array(Box::new(base_type)))
+                t = Type::Array(Box::new(t));
             } else {
-                println!("current: {:?}", self.current());
-                Err("invalid array syntax".to_string())
+                return Err(self.invalid_array());
             }
-        } else {
-            Ok(base_type)
         }
-    }
 
-    fn parse_visibility(&mut self) -> Result<Visibility, String> {
-        let vis = match self.current() {
-            Token::External => {
-                self.advance();
-                Visibility::External
-            }
-            Token::Public => {
-                self.advance();
-                Visibility::Public
-            }
-            Token::Internal => {
-                self.advance();
-                Visibility::Internal
-            }
-            Token::Private => {
-                self.advance();
-                Visibility::Private
-            }
-            _ => Visibility::Public,
-        };
+        Ok(t)
+    }
 
-        Ok(vis)
+    // mapping(address owner => uint256 balance), names are allowed since 0.8.18
+    fn skip_mapping_name(&mut self) {
+        if matches!(self.current(), Token::Identifier(_)) {
+            self.advance();
+        }
     }
 
-    fn parse_state_mutability(&mut self) -> Option<Mutability> {
-        match self.current() {
-            Token::Pure => {
-                self.advance();
-                Some(Mutability::Pure)
-            }
-            Token::View => {
-                self.advance();
+    // function (uint256) external view returns (bool); a variable's own visibility
+    // (public, private) follows the type and is not part of it
+    fn parse_function_type(&mut self) -> Result<Type, ParseError> {
+        self.expect(Token::Function)?;
+        self.expect(Token::LeftParen)?;
+        let parameters = self.parse_parameter_list()?;
+        self.expect(Token::RightParen)?;
+
+        let mut visibility = Visibility::Internal;
+        let mut mutability = None;
+        let mut returns = Vec::new();
+        loop {
+            match self.current() {
+                Token::External | Token::Internal => visibility = self.parse_visibility()?,
+                Token::Pure | Token::View | Token::Payable => {
+                    mutability = self.parse_state_mutability();
+                }
+                Token::Returns => {
+                    self.advance();
+                    self.expect(Token::LeftParen)?;
+                    returns = self.parse_parameter_list()?;
+                    self.expect(Token::RightParen)?;
+                }
+                _ => break,
+            }
+        }
+
+        Ok(Type::Function(FunctionType {
+            parameters,
+            visibility,
+            mutability,
+            returns,
+        }))
+    }
+
+    fn parse_base_type(&mut self) -> Result<Type, ParseError> {
+        let base_type = match self.current() {
+            Token::Type(t) => {
+                let type_name = t.clone();
+                if !is_valid_elementary(&type_name) {
+                    return Err(ParseError::InvalidType {
+                        name: type_name,
+                        span: self.span(),
+                    });
+                }
+                self.advance();
+                // address payable is still an address in the ABI, only its internalType differs
+                if type_name == "address" && self.current() == &Token::Payable {
+                    self.advance();
+                    Type::Elementary("address payable".to_string())
+                } else {
+                    Type::Elementary(type_name)
+                }
+            }
+            Token::Identifier(t) => {
+                // user-defined, possibly qualified as `Lib.Order`
+                let mut type_name = t.clone();
+                self.advance();
+                while self.current() == &Token::Dot {
+                    self.advance();
+                    match self.current() {
+                        Token::Identifier(part) => {
+                            type_name.push('.');
+                            type_name.push_str(part);
+                            self.advance();
+                        }
+                        _ => return Err(self.expected("identifier")),
+                    }
+                }
+                Type::UserDefined(type_name)
+            }
+            _ => return Err(self.expected("type")),
+        };
+
+        Ok(base_type)
+    }
+
+    fn parse_visibility(&mut self) -> Result<Visibility, ParseError> {
+        let vis = match self.current() {
+            Token::External => {
+                self.advance();
+                Visibility::External
+            }
+            Token::Public => {
+                self.advance();
+                Visibility::Public
+            }
+            Token::Internal => {
+                self.advance();
+                Visibility::Internal
+            }
+            Token::Private => {
+                self.advance();
+                Visibility::Private
+            }
+            _ => Visibility::Public,
+        };
+
+        Ok(vis)
+    }
+
+    fn parse_state_mutability(&mut self) -> Option<Mutability> {
+        match self.current() {
+            Token::Pure => {
+                self.advance();
+                Some(Mutability::Pure)
+            }
+            Token::View => {
+                self.advance();
                 Some(Mutability::View)
             }
             Token::Payable => {
@@ -285,34 +783,377 @@ impl Parser {
         }
     }
 
-    pub fn parse_all_symbols(&mut self) -> (Vec<Function>, Vec<Variable>) {
-        let mut functions = Vec::new();
-        let mut variables = Vec::new();
+    pub fn parse_import(&mut self) -> Result<Import, ParseError> {
+        self.expect(Token::Import)?;
 
-        while self.current() != &Token::Eof {
-            if self.current() == &Token::Function {
-                match self.parse_function() {
-                    Ok(func) => functions.push(func),
-                    Err(_) => {
+        let mut alias = None;
+        let mut symbols = Vec::new();
+
+        let path = match self.current() {
+            // import "./A.sol" [as A];
+            Token::StringLiteral(p) => {
+                let path = p.clone();
+                self.advance();
+                if self.current() == &Token::As {
+                    self.advance();
+                    alias = Some(self.parse_identifier()?);
+                }
+                path
+            }
+            // import * as A from "./A.sol";
+            Token::Unknown('*') => {
+                self.advance();
+                self.expect(Token::As)?;
+                alias = Some(self.parse_identifier()?);
+                self.parse_import_source()?
+            }
+            // import {B, C as D} from "./A.sol";
+            Token::LeftBrace => {
+                self.advance();
+                while self.current() != &Token::RightBrace {
+                    let name = self.parse_identifier()?;
+                    let symbol_alias = if self.current() == &Token::As {
                         self.advance();
+                        Some(self.parse_identifier()?)
+                    } else {
+                        None
+                    };
+                    symbols.push((name, symbol_alias));
+
+                    if self.current() == &Token::Comma {
+                        self.advance();
+                    } else {
+                        break;
                     }
                 }
-            } else if matches!(self.current(), Token::Type(_) | Token::Mapping) {
+                self.expect(Token::RightBrace)?;
+                self.parse_import_source()?
+            }
+            _ => return Err(self.expected("import path or symbols")),
+        };
+
+        self.expect(Token::Semicolon)?;
+
+        Ok(Import {
+            path,
+            alias,
+            symbols,
+        })
+    }
+
+    // from "./A.sol"
+    fn parse_import_source(&mut self) -> Result<String, ParseError> {
+        match self.current() {
+            Token::Identifier(kw) if kw == "from" => self.advance(),
+            _ => return Err(self.expected("from")),
+        }
+
+        match self.current() {
+            Token::StringLiteral(p) => {
+                let path = p.clone();
+                self.advance();
+                Ok(path)
+            }
+            _ => Err(self.expected("import path")),
+        }
+    }
+
+    fn parse_identifier(&mut self) -> Result<String, ParseError> {
+        match self.current() {
+            Token::Identifier(n) => {
+                let name = n.clone();
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(self.expected("identifier")),
+        }
+    }
+
+    // contract A is B, C(1) { ... }
+    pub fn parse_contract(&mut self) -> Result<ContractDefinition, ParseError> {
+        let kind = match self.current() {
+            Token::Abstract => {
+                self.advance();
+                self.expect(Token::Contract)?;
+                ContractKind::Abstract
+            }
+            Token::Contract => {
+                self.advance();
+                ContractKind::Contract
+            }
+            Token::Interface => {
+                self.advance();
+                ContractKind::Interface
+            }
+            Token::Library => {
+                self.advance();
+                ContractKind::Library
+            }
+            _ => return Err(self.expected("contract, interface or library")),
+        };
+
+        let span = self.span();
+        let name = self.parse_identifier()?;
+
+        // libraries are not types, contracts and interfaces are passed as addresses
+        if kind != ContractKind::Library {
+            self.symbols.add_contract(&name);
+        }
+
+        let mut bases = Vec::new();
+        if self.current() == &Token::Is {
+            self.advance();
+            loop {
+                bases.push(self.parse_name("base contract")?);
+                // constructor arguments passed in the inheritance list
+                self.advance_parenthesized();
+
+                if self.current() == &Token::Comma {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+        }
+        self.symbols.add_bases(&name, &bases);
+
+        self.expect(Token::LeftBrace)?;
+
+        let mut decls = Declarations::default();
+        while !matches!(self.current(), Token::RightBrace | Token::Eof) {
+            self.parse_declaration(Some(&name), &mut decls);
+        }
+
+        self.expect(Token::RightBrace)?;
+
+        // before 0.4.22 the constructor was the function named after the contract
+        let (constructors, functions): (Vec<Function>, Vec<Function>) =
+            decls.functions.into_iter().partition(|f| f.name == name);
+        let mut special_functions = decls.special_functions;
+        special_functions.extend(constructors.into_iter().map(|f| SpecialFunction {
+            kind: SpecialKind::Constructor,
+            parameters: f.parameters,
+            mutability: f.mutability,
+            span: f.span,
+        }));
+
+        Ok(ContractDefinition {
+            kind,
+            name,
+            bases,
+            functions,
+            variables: decls.variables,
+            events: decls.events,
+            errors: decls.errors,
+            special_functions,
+            span,
+        })
+    }
+
+    // one declaration at file level (`scope` is None) or inside a contract body
+    fn parse_declaration(&mut self, scope: Option<&str>, decls: &mut Declarations) {
+        match self.current() {
+            Token::Pragma(pragma) => {
+                if let Some(req) = pragma.strip_prefix("solidity") {
+                    self.solidity = VersionReq::parse(req);
+                }
+                self.advance();
+            }
+            Token::Import => match self.parse_import() {
+                Ok(import) => decls.imports.push(import),
+                Err(e) => self.skip_declaration(e),
+            },
+            Token::Struct => match self.parse_struct() {
+                Ok(def) => self.symbols.add_struct(scope, def),
+                Err(e) => self.skip_declaration(e),
+            },
+            Token::Enum => match self.parse_enum() {
+                Ok(def) => self.symbols.add_enum(scope, def),
+                Err(e) => self.skip_declaration(e),
+            },
+            // `type Price is uint128;`, not `type(uint256).max`
+            Token::TypeDef
+                if matches!(self.peek(), Token::Identifier(_)) && self.peek_at(2) == &Token::Is =>
+            {
+                match self.parse_value_type() {
+                    Ok(def) => self.symbols.add_value_type(scope, def),
+                    Err(e) => self.skip_declaration(e),
+                }
+            }
+            Token::Event => match self.parse_event() {
+                Ok(event) => decls.events.push(event),
+                Err(e) => self.skip_declaration(e),
+            },
+            Token::Identifier(s)
+                if s == "error"
+                    && matches!(self.peek(), Token::Identifier(_))
+                    && self.peek_at(2) == &Token::LeftParen =>
+            {
+                match self.parse_error() {
+                    Ok(error) => decls.errors.push(error),
+                    Err(e) => self.skip_declaration(e),
+                }
+            }
+            // a state variable of function type, `function (uint256) external callback;`,
+            // or the unnamed fallback function of old compilers
+            Token::Function if scope.is_some() && self.peek() == &Token::LeftParen => {
+                let start = self.position;
                 match self.parse_variable() {
-                    Ok(var) => variables.push(var),
-                    Err(_) => {
-                        self.advance();
+                    Ok(var) => decls.variables.push(var),
+                    Err(variable_error) => {
+                        let variable_end = self.position;
+                        self.position = start;
+                        match self.parse_special_function(SpecialKind::Fallback) {
+                            Ok(func) => decls.special_functions.push(func),
+                            // report whichever reading got further
+                            Err(e) if self.position >= variable_end => self.skip_declaration(e),
+                            Err(_) => {
+                                self.position = variable_end;
+                                self.skip_declaration(variable_error);
+                            }
+                        }
                     }
                 }
+            }
+            Token::Function => match self.parse_function() {
+                Ok(func) => decls.functions.push(func),
+                Err(e) => self.skip_declaration(e),
+            },
+            Token::Constructor if scope.is_some() => {
+                match self.parse_special_function(SpecialKind::Constructor) {
+                    Ok(func) => decls.special_functions.push(func),
+                    Err(e) => self.skip_declaration(e),
+                }
+            }
+            Token::Identifier(s)
+                if scope.is_some()
+                    && matches!(s.as_str(), "fallback" | "receive")
+                    && self.peek() == &Token::LeftParen =>
+            {
+                let kind = if s == "fallback" {
+                    SpecialKind::Fallback
+                } else {
+                    SpecialKind::Receive
+                };
+                match self.parse_special_function(kind) {
+                    Ok(func) => decls.special_functions.push(func),
+                    Err(e) => self.skip_declaration(e),
+                }
+            }
+            // headers and bodies we have no selectors for; their parameters are not variables
+            Token::Constructor | Token::Modifier => self.skip_to_end(),
+            Token::Using => self.skip_using(),
+            // state variables only exist inside contracts, where any other declaration
+            // starts with its type
+            Token::Type(_) | Token::Mapping | Token::Identifier(_) if scope.is_some() => {
+                match self.parse_variable() {
+                    Ok(var) => decls.variables.push(var),
+                    Err(e) => self.skip_declaration(e),
+                }
+            }
+            // file-level constants, `uint256 constant MAX = type(uint256).max;`, have no getter
+            Token::Type(_) | Token::Mapping | Token::Identifier(_) => self.skip_to_end(),
+            Token::LeftBrace => self.advance_braced_block(),
+            _ => self.advance(),
+        }
+    }
+
+    // `using {add, sub as +} for Fixed global;` ends at the semicolon, the braces are a list
+    fn skip_using(&mut self) {
+        while !matches!(self.current(), Token::Eof | Token::RightBrace) {
+            match self.current() {
+                Token::Semicolon => {
+                    self.advance();
+                    return;
+                }
+                Token::LeftBrace => self.advance_braced_block(),
+                _ => self.advance(),
+            }
+        }
+    }
+
+    // records why a declaration failed to parse, then skips the rest of it
+    fn skip_declaration(&mut self, error: ParseError) {
+        self.diagnostics.push(error);
+        self.skip_to_end();
+    }
+
+    // skips up to and including the `;` or braced body ending the current declaration
+    fn skip_to_end(&mut self) {
+        while self.current() != &Token::Eof {
+            match self.current() {
+                Token::Semicolon => {
+                    self.advance();
+                    return;
+                }
+                Token::LeftBrace => {
+                    self.advance_braced_block();
+                    return;
+                }
+                // end of the enclosing contract
+                Token::RightBrace => return,
+                Token::LeftParen => self.advance_parenthesized(),
+                _ => self.advance(),
+            }
+        }
+    }
+
+    pub fn parse_all_symbols(&mut self) -> SourceUnit {
+        let mut contracts = Vec::new();
+        let mut decls = Declarations::default();
+
+        while self.current() != &Token::Eof {
+            if matches!(
+                self.current(),
+                Token::Contract | Token::Abstract | Token::Interface | Token::Library
+            ) {
+                match self.parse_contract() {
+                    Ok(contract) => contracts.push(contract),
+                    Err(e) => self.skip_declaration(e),
+                }
             } else {
-                self.advance();
+                self.parse_declaration(None, &mut decls);
             }
         }
 
-        (functions, variables)
+        let mut unit = SourceUnit {
+            solidity: self.solidity.clone(),
+            imports: decls.imports,
+            contracts,
+            functions: decls.functions,
+            events: decls.events,
+            errors: decls.errors,
+        };
+
+        // types may be declared after their first use, so resolve once everything is seen
+        self.symbols.resolve_source_unit(&mut unit);
+
+        unit
     }
 }
 
+// members collected while walking a contract body or the file level
+#[derive(Default)]
+struct Declarations {
+    imports: Vec<Import>,
+    functions: Vec<Function>,
+    variables: Vec<Variable>,
+    events: Vec<Event>,
+    errors: Vec<Error>,
+    special_functions: Vec<SpecialFunction>,
+}
+
+// everything between a function's parameter list and its body
+#[derive(Default)]
+struct FunctionSpecifiers {
+    visibility: Option<Visibility>,
+    mutability: Option<Mutability>,
+    is_virtual: bool,
+    overrides: Option<Vec<String>>,
+    modifiers: Vec<String>,
+    returns: Option<Vec<Parameter>>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -329,7 +1170,7 @@ mod tests {
         assert_eq!(var.name, "number");
         assert_eq!(var.visibility, Visibility::Public);
         assert_eq!(var.type_name.canonical(), "uint256");
-        assert_eq!(var.returns.type_name.canonical(), "uint256");
+        assert_eq!(var.getter_returns()[0].type_name.canonical(), "uint256");
     }
 
     #[test]
@@ -373,9 +1214,129 @@ mod tests {
         assert_eq!(func.signature(), "foo(uint256)");
     }
 
+    #[test]
+    fn elementary_types() {
+        let input = "function foo(int a, byte b, fixed c, ufixed d, fixed64x10 e, address payable f, bytes32 g) external;";
+        let mut parser = Parser::new(Lexer::new(input).tokenize());
+        let func = parser.parse_function().unwrap();
+
+        assert_eq!(
+            func.signature(),
+            "foo(int256,bytes1,fixed128x18,ufixed128x18,fixed64x10,address,bytes32)"
+        );
+
+        for invalid in [
+            "uint7",
+            "int264",
+            "bytes0",
+            "bytes33",
+            "fixed128x81",
+            "ufixed7x2",
+        ] {
+            let input = format!("function foo({} x) external;", invalid);
+            let mut parser = Parser::new(Lexer::new(&input).tokenize());
+            assert_eq!(
+                parser.parse_function().unwrap_err().to_string(),
+                format!("invalid type {}", invalid)
+            );
+        }
+
+        // names that merely start like a type
+        let input = "function foo(uint interval, uint bytesLeft) external;";
+        let mut parser = Parser::new(Lexer::new(input).tokenize());
+        assert_eq!(parser.parse_function().unwrap().parameters.len(), 2);
+    }
+
+    #[test]
+    fn function_type_parameters() {
+        let input = r#"
+            contract Scheduler {
+                function (uint256) external returns (bool) public callback;
+                function () internal view[] private hooks;
+
+                function schedule(function (uint256) external returns (bool) cb, uint256 at) external {}
+                function batch(function () external payable[] calldata calls) external {}
+            }
+        "#;
+        let mut parser = Parser::new(Lexer::new(input).tokenize());
+        let unit = parser.parse_all_symbols();
+        let contract = &unit.contracts[0];
+
+        let functions: Vec<String> = contract.functions.iter().map(|f| f.signature()).collect();
+        assert_eq!(
+            functions,
+            vec!["schedule(function,uint256)", "batch(function[])"]
+        );
+
+        let callback = &contract.variables[0];
+        assert_eq!(callback.signature(), "callback()");
+        assert_eq!(callback.visibility, Visibility::Public);
+        assert_eq!(
+            callback.getter_returns()[0].type_name.canonical(),
+            "function"
+        );
+        let Type::Function(func) = &callback.type_name else {
+            panic!("expected a function type, got {:?}", callback.type_name);
+        };
+        assert_eq!(func.visibility, Visibility::External);
+        assert_eq!(func.parameters[0].type_name.canonical(), "uint256");
+        assert_eq!(func.returns[0].type_name.canonical(), "bool");
+
+        let hooks = &contract.variables[1];
+        assert_eq!(hooks.visibility, Visibility::Private);
+        assert!(
+            matches!(&hooks.type_name, Type::Array(inner) if matches!(**inner, Type::Function(_)))
+        );
+        assert!(parser.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn library_signatures() {
+        let input = r#"
+            struct Point { uint256 x; uint256 y; }
+
+            library Positions {
+                struct Position { address owner; uint128 size; }
+                enum Side { Long, Short }
+
+                function open(Position storage p, Side side, uint256[] storage ids) external {}
+                function close(mapping(address => Position) storage book, Position[] memory batch) public {}
+                function shift(Point calldata by, IERC20 token) external {}
+            }
+
+            interface IERC20 {}
+
+            contract Exchange {
+                function trade(Positions.Position calldata p, Positions.Side side) external {}
+            }
+        "#;
+        let mut parser = Parser::new(Lexer::new(input).tokenize());
+        let unit = parser.parse_all_symbols();
+
+        let library: Vec<String> = unit.contracts[0]
+            .functions
+            .iter()
+            .map(|f| f.library_signature())
+            .collect();
+        assert_eq!(
+            library,
+            vec![
+                "open(Positions.Position storage,Positions.Side,uint256[] storage)",
+                "close(mapping(address => Positions.Position) storage,Positions.Position[])",
+                "shift(Point,address)",
+            ]
+        );
+
+        // the same types in a contract are plain ABI types
+        assert_eq!(
+            unit.contracts[2].functions[0].signature(),
+            "trade((address,uint128),uint8)"
+        );
+    }
+
     #[test]
     fn arrays() {
-        let input = "function bar(uint256[] memory arr, uint256[3] memory fixed) external";
+        let input = "function bar(uint256[] memory arr, uint256[3] memory sized) external";
         let mut lexer = Lexer::new(input);
         let tokens = lexer.tokenize();
         let mut parser = Parser::new(tokens);
@@ -384,6 +1345,580 @@ mod tests {
         assert_eq!(func.signature(), "bar(uint256[],uint256[3])");
     }
 
+    #[test]
+    fn using_for() {
+        let input = r#"
+            library Math {
+                function add(uint256 a, uint256 b) internal pure returns (uint256) {}
+                function sub(uint256 a, uint256 b) internal pure returns (uint256) {}
+            }
+
+            contract Counter {
+                using {Math.add, Math.sub} for uint256;
+                using Math for *;
+                uint256 public count;
+            }
+        "#;
+        let mut lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer.tokenize());
+        let unit = parser.parse_all_symbols();
+        let counter = &unit.contracts[1];
+
+        assert!(parser.diagnostics().is_empty());
+        assert_eq!(counter.variables.len(), 1);
+        assert_eq!(counter.variables[0].signature(), "count()");
+    }
+
+    #[test]
+    fn struct_members_in_declaring_scope() {
+        let input = r#"
+            library L {
+                struct A { uint x; }
+                struct B { A a; }
+            }
+
+            contract First {
+                struct Order { uint256 x; }
+            }
+
+            contract Base {
+                struct Order { address y; bool z; }
+            }
+
+            contract C is Base {
+                struct A { bool y; }
+                function f(L.B calldata b) external {}
+                function fc(Order calldata o) external {}
+            }
+        "#;
+        let mut lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer.tokenize());
+        let unit = parser.parse_all_symbols();
+        let c = &unit.contracts[3];
+
+        // `A` in L.B is L's, not the one C declares
+        assert_eq!(c.functions[0].signature(), "f(((uint256)))");
+        // inherited declarations win over unrelated ones of the same name
+        assert_eq!(c.functions[1].signature(), "fc((address,bool))");
+    }
+
+    #[test]
+    fn struct_parameters() {
+        let input = r#"
+            library Types {
+                struct Asset { address token; uint256 amount; }
+            }
+
+            contract Exchange {
+                struct Order {
+                    address maker;
+                    Types.Asset asset;
+                    bytes data;
+                }
+
+                function submit(Order calldata o) external {}
+                function batch(Order[] calldata orders, uint fee) external {}
+                function swap(Types.Asset memory a) external {}
+            }
+        "#;
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let unit = parser.parse_all_symbols();
+        let exchange = &unit.contracts[1];
+        let functions = &exchange.functions;
+
+        assert!(exchange.variables.is_empty());
+        assert_eq!(
+            functions[0].signature(),
+            "submit((address,(address,uint256),bytes))"
+        );
+        assert_eq!(
+            functions[1].signature(),
+            "batch((address,(address,uint256),bytes)[],uint256)"
+        );
+        assert_eq!(functions[2].signature(), "swap((address,uint256))");
+    }
+
+    #[test]
+    fn enum_contract_and_value_type_parameters() {
+        let input = r#"
+            type Price is uint128;
+
+            interface IERC20 {}
+
+            contract Market {
+                enum Side { Buy, Sell }
+                type Qty is uint64;
+
+                function place(Side side, Price price, Market.Qty qty, IERC20 token) external {}
+                function settle(Market other, Side[] calldata sides) external {}
+            }
+        "#;
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let unit = parser.parse_all_symbols();
+        let functions = &unit.contracts[1].functions;
+
+        assert_eq!(
+            functions[0].signature(),
+            "place(uint8,uint128,uint64,address)"
+        );
+        assert_eq!(functions[1].signature(), "settle(address,uint8[])");
+    }
+
+    #[test]
+    fn events() {
+        let input = r#"
+            contract Token {
+                event Transfer(address indexed from, address indexed to, uint256 value);
+                event Raw(bytes32 indexed, uint) anonymous;
+            }
+        "#;
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let unit = parser.parse_all_symbols();
+        let token = &unit.contracts[0];
+
+        assert!(token.variables.is_empty());
+        assert_eq!(token.events.len(), 2);
+
+        let transfer = &token.events[0];
+        assert_eq!(transfer.signature(), "Transfer(address,address,uint256)");
+        assert!(!transfer.anonymous);
+        assert!(transfer.parameters[0].indexed);
+        assert_eq!(transfer.parameters[0].name.as_deref(), Some("from"));
+        assert!(!transfer.parameters[2].indexed);
+
+        let raw = &token.events[1];
+        assert_eq!(raw.signature(), "Raw(bytes32,uint256)");
+        assert!(raw.anonymous);
+        assert!(raw.parameters[0].indexed);
+        assert_eq!(raw.parameters[0].name, None);
+    }
+
+    #[test]
+    fn errors() {
+        let input = r#"
+            error Unauthorized();
+
+            contract Vault {
+                struct Limit { uint256 cap; address owner; }
+
+                error InsufficientBalance(uint256 available, uint256 required);
+                error OverLimit(Limit limit);
+            }
+        "#;
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let unit = parser.parse_all_symbols();
+
+        let vault = &unit.contracts[0];
+
+        assert!(vault.variables.is_empty());
+        assert!(vault.functions.is_empty());
+        assert_eq!(unit.errors.len(), 1);
+        assert_eq!(unit.errors[0].signature(), "Unauthorized()");
+        assert_eq!(vault.errors.len(), 2);
+        assert_eq!(
+            vault.errors[0].signature(),
+            "InsufficientBalance(uint256,uint256)"
+        );
+        assert_eq!(vault.errors[1].signature(), "OverLimit((uint256,address))");
+    }
+
+    #[test]
+    fn error_is_not_reserved() {
+        let input = r#"
+            contract Relay {
+                uint256 public error;
+                error Failed(uint256 error);
+                function f(uint error, bytes memory data) external {}
+            }
+        "#;
+        let mut lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer.tokenize());
+        let unit = parser.parse_all_symbols();
+
+        let relay = &unit.contracts[0];
+        assert!(parser.diagnostics().is_empty());
+        assert_eq!(relay.variables[0].signature(), "error()");
+        assert_eq!(relay.errors[0].signature(), "Failed(uint256)");
+        assert_eq!(relay.functions[0].signature(), "f(uint256,bytes)");
+    }
+
+    #[test]
+    fn contracts() {
+        let input = r#"
+            interface IERC20 {
+                function totalSupply() external view returns (uint256);
+            }
+
+            abstract contract Ownable {
+                address public owner;
+            }
+
+            library Math {
+                function max(uint a, uint b) internal pure returns (uint) { return a > b ? a : b; }
+            }
+
+            contract Token is IERC20, Ownable, Base.Nested(1, msg.sender) {
+                function totalSupply() external view returns (uint256) { return 0; }
+            }
+        "#;
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let unit = parser.parse_all_symbols();
+
+        assert_eq!(unit.contracts.len(), 4);
+        assert!(unit.functions.is_empty());
+
+        let kinds: Vec<(ContractKind, &str)> = unit
+            .contracts
+            .iter()
+            .map(|c| (c.kind, c.name.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (ContractKind::Interface, "IERC20"),
+                (ContractKind::Abstract, "Ownable"),
+                (ContractKind::Library, "Math"),
+                (ContractKind::Contract, "Token"),
+            ]
+        );
+
+        assert_eq!(unit.contracts[0].functions[0].name, "totalSupply");
+        assert_eq!(unit.contracts[1].variables[0].name, "owner");
+        assert_eq!(unit.contracts[2].functions[0].name, "max");
+        assert_eq!(
+            unit.contracts[3].bases,
+            vec!["IERC20", "Ownable", "Base.Nested"]
+        );
+        assert_eq!(unit.contracts[3].functions.len(), 1);
+    }
+
+    #[test]
+    fn virtual_and_override() {
+        let input = r#"
+            contract Token is IERC20, Ownable {
+                uint256 public override totalSupply;
+                function transfer(address to, uint amount) external virtual override(IERC20, Base) returns (bool) {}
+                function owner() public view override returns (address) {}
+            }
+        "#;
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let unit = parser.parse_all_symbols();
+        let token = &unit.contracts[0];
+
+        assert_eq!(token.variables[0].name, "totalSupply");
+        assert_eq!(token.variables[0].overrides, Some(vec![]));
+
+        let transfer = &token.functions[0];
+        assert!(transfer.is_virtual);
+        assert_eq!(
+            transfer.overrides,
+            Some(vec!["IERC20".to_string(), "Base".to_string()])
+        );
+        assert_eq!(transfer.returns.as_ref().map(|r| r.len()), Some(1));
+
+        let owner = &token.functions[1];
+        assert!(!owner.is_virtual);
+        assert_eq!(owner.mutability, Some(Mutability::View));
+        assert_eq!(owner.overrides, Some(vec![]));
+    }
+
+    #[test]
+    fn imports() {
+        let input = r#"
+            import "./IERC20.sol";
+            import "./Math.sol" as M;
+            import * as Lib from "lib/Lib.sol";
+            import {Order, Asset as A} from "src/types/Order.sol";
+        "#;
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let unit = parser.parse_all_symbols();
+
+        assert_eq!(
+            unit.imports,
+            vec![
+                Import {
+                    path: "./IERC20.sol".to_string(),
+                    alias: None,
+                    symbols: vec![],
+                },
+                Import {
+                    path: "./Math.sol".to_string(),
+                    alias: Some("M".to_string()),
+                    symbols: vec![],
+                },
+                Import {
+                    path: "lib/Lib.sol".to_string(),
+                    alias: Some("Lib".to_string()),
+                    symbols: vec![],
+                },
+                Import {
+                    path: "src/types/Order.sol".to_string(),
+                    alias: None,
+                    symbols: vec![
+                        ("Order".to_string(), None),
+                        ("Asset".to_string(), Some("A".to_string())),
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn specifiers_in_any_order() {
+        let input = r#"
+            contract Vault {
+                function f() public virtual override onlyOwner returns (uint) {}
+                function g() onlyRole(ADMIN) external {}
+                function h(uint a) override(A, B) nonReentrant payable whenNotPaused() external returns (bool ok);
+                function i() constant returns (uint) {}
+                uint256 constant public LIMIT = 10;
+                address immutable override owner;
+            }
+        "#;
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let unit = parser.parse_all_symbols();
+        let vault = &unit.contracts[0];
+
+        assert_eq!(vault.functions.len(), 4);
+
+        let f = &vault.functions[0];
+        assert_eq!(f.visibility, Visibility::Public);
+        assert!(f.is_virtual);
+        assert_eq!(f.overrides, Some(vec![]));
+        assert_eq!(f.modifiers, vec!["onlyOwner"]);
+        assert_eq!(f.returns.as_ref().map(|r| r.len()), Some(1));
+
+        let g = &vault.functions[1];
+        assert_eq!(g.visibility, Visibility::External);
+        assert_eq!(g.modifiers, vec!["onlyRole"]);
+
+        let h = &vault.functions[2];
+        assert_eq!(h.signature(), "h(uint256)");
+        assert_eq!(h.visibility, Visibility::External);
+        assert_eq!(h.mutability, Some(Mutability::Payable));
+        assert_eq!(h.modifiers, vec!["nonReentrant", "whenNotPaused"]);
+        assert_eq!(h.overrides, Some(vec!["A".to_string(), "B".to_string()]));
+
+        let i = &vault.functions[3];
+        assert_eq!(i.mutability, Some(Mutability::View));
+        assert!(i.modifiers.is_empty());
+
+        assert_eq!(vault.variables.len(), 2);
+        assert_eq!(vault.variables[0].name, "LIMIT");
+        assert_eq!(vault.variables[0].visibility, Visibility::Public);
+        assert_eq!(vault.variables[1].name, "owner");
+        assert_eq!(vault.variables[1].overrides, Some(vec![]));
+    }
+
+    #[test]
+    fn erc20_asset() {
+        let input = include_str!("../assets/erc20.sol");
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let unit = parser.parse_all_symbols();
+
+        assert_eq!(unit.contracts.len(), 1);
+        let erc20 = &unit.contracts[0];
+
+        let variables: Vec<String> = erc20.variables.iter().map(|v| v.signature()).collect();
+        assert_eq!(
+            variables,
+            vec![
+                "totalSupply()",
+                "balanceOf(address)",
+                "allowance(address,address)",
+                "name()",
+                "symbol()",
+                "decimals()",
+            ]
+        );
+
+        let functions: Vec<String> = erc20.functions.iter().map(|f| f.signature()).collect();
+        assert_eq!(
+            functions,
+            vec![
+                "transfer(address,uint256)",
+                "approve(address,uint256)",
+                "transferFrom(address,address,uint256)",
+                "_mint(address,uint256)",
+                "_burn(address,uint256)",
+                "mint(address,uint256)",
+                "burn(address,uint256)",
+            ]
+        );
+
+        let events: Vec<String> = erc20.events.iter().map(|e| e.signature()).collect();
+        assert_eq!(
+            events,
+            vec![
+                "Transfer(address,address,uint256)",
+                "Approval(address,address,uint256)",
+            ]
+        );
+    }
+
+    #[test]
+    fn no_getters_from_headers() {
+        let input = r#"
+            contract Token is ERC20 {
+                using SafeMath for uint256;
+
+                struct Position { uint256 size; address owner; }
+                event Moved(uint256 indexed amount, address to);
+                error Failed(uint256 code);
+
+                IERC20 public immutable asset;
+                Position internal position;
+                address payable public treasury = payable(msg.sender);
+                uint256 public cap = computeCap(1, max(2, 3));
+
+                constructor(uint8 _decimals, address _owner) ERC20("Token", "TKN") {
+                    uint256 local = _decimals;
+                }
+
+                modifier onlyOwner(address caller) {
+                    require(caller == owner);
+                    _;
+                }
+
+                fallback() external payable {}
+                receive() external payable {}
+            }
+        "#;
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let unit = parser.parse_all_symbols();
+        let token = &unit.contracts[0];
+
+        let variables: Vec<&str> = token.variables.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(variables, vec!["asset", "position", "treasury", "cap"]);
+        assert_eq!(
+            token.variables[0].type_name,
+            Type::UserDefined("IERC20".to_string())
+        );
+        assert_eq!(token.variables[1].visibility, Visibility::Internal);
+        assert_eq!(token.variables[2].type_name.canonical(), "address");
+        assert!(token.functions.is_empty());
+        assert_eq!(token.events.len(), 1);
+        assert_eq!(token.errors.len(), 1);
+    }
+
+    #[test]
+    fn default_visibility() {
+        let modern = r#"
+            pragma solidity ^0.8.13;
+            contract Counter {
+                uint256 counter;
+                uint256 public number;
+                function f() {}
+            }
+        "#;
+        let legacy = r#"
+            pragma solidity ^0.4.24;
+            contract Counter {
+                uint256 counter;
+                function f() {}
+                function g() constant returns (uint256) {}
+            }
+        "#;
+
+        let mut parser = Parser::new(Lexer::new(modern).tokenize());
+        let unit = parser.parse_all_symbols();
+        let counter = &unit.contracts[0];
+        assert!(unit.solidity.is_some());
+        assert_eq!(counter.variables[0].visibility, Visibility::Internal);
+        assert_eq!(counter.variables[1].visibility, Visibility::Public);
+        assert_eq!(counter.functions[0].visibility, Visibility::Internal);
+
+        let mut parser = Parser::new(Lexer::new(legacy).tokenize());
+        let unit = parser.parse_all_symbols();
+        let counter = &unit.contracts[0];
+        assert_eq!(counter.variables[0].visibility, Visibility::Internal);
+        assert_eq!(counter.functions[0].visibility, Visibility::Public);
+        assert_eq!(counter.functions[1].visibility, Visibility::Public);
+        assert_eq!(counter.functions[1].mutability, Some(Mutability::View));
+    }
+
+    // canonical type of an ABI JSON parameter, tuples spelled out from their components
+    fn abi_type(param: &serde_json::Value) -> String {
+        let t = param["type"].as_str().unwrap();
+        match t.strip_prefix("tuple") {
+            Some(suffix) => {
+                let components: Vec<String> = param["components"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(abi_type)
+                    .collect();
+                format!("({}){}", components.join(","), suffix)
+            }
+            None => t.to_string(),
+        }
+    }
+
+    #[test]
+    fn getters_match_solc_abi() {
+        let input = include_str!("../tests/fixtures/getters.sol");
+        let abi: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/fixtures/getters.abi.json")).unwrap();
+        let entries = abi.as_array().unwrap();
+
+        let mut parser = Parser::new(Lexer::new(input).tokenize());
+        let unit = parser.parse_all_symbols();
+        let getters = &unit.contracts[1];
+
+        assert_eq!(getters.variables.len(), entries.len());
+
+        for var in &getters.variables {
+            let entry = entries
+                .iter()
+                .find(|e| e["name"] == var.name.as_str())
+                .unwrap_or_else(|| panic!("{} missing from the abi", var.name));
+
+            let inputs: Vec<String> = entry["inputs"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(abi_type)
+                .collect();
+            assert_eq!(
+                var.signature(),
+                format!("{}({})", var.name, inputs.join(","))
+            );
+
+            let outputs: Vec<String> = entry["outputs"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(abi_type)
+                .collect();
+            let returns: Vec<String> = var
+                .getter_returns()
+                .iter()
+                .map(|r| r.type_name.canonical())
+                .collect();
+            assert_eq!(returns, outputs, "{}", var.name);
+        }
+    }
+
     #[test]
     fn function_with_body_and_public_visibility() {
         let input = "function setValue(uint256 _value) public { value = _value; }";
@@ -398,4 +1933,165 @@ mod tests {
         assert_eq!(func.visibility, Visibility::Public);
         assert_eq!(func.signature(), "setValue(uint256)");
     }
+
+    #[test]
+    fn braces_inside_string_literals() {
+        let input = r#"
+            contract Vault {
+                function withdraw() external {
+                    require(msg.sender != address(0), "} function steal() external {");
+                    emit Log(hex"7d7b", unicode"}} ☠");
+                }
+                function deposit() external payable {}
+            }
+        "#;
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let unit = parser.parse_all_symbols();
+
+        let functions: Vec<String> = unit.contracts[0]
+            .functions
+            .iter()
+            .map(|f| f.signature())
+            .collect();
+        assert_eq!(functions, vec!["withdraw()", "deposit()"]);
+    }
+
+    #[test]
+    fn spans_and_errors() {
+        let input = "contract A {\n    uint public x;\n    function f(uint a b) external;\n    event E();\n}";
+        let mut parser = Parser::with_spans(Lexer::new(input).tokenize_spanned());
+        let unit = parser.parse_all_symbols();
+        let contract = &unit.contracts[0];
+
+        assert_eq!((contract.span.line, contract.span.column), (1, 10));
+        assert_eq!(contract.variables[0].span.line, 2);
+        assert_eq!(contract.events[0].span.line, 4);
+        assert!(contract.functions.is_empty());
+
+        let error = &parser.diagnostics()[0];
+        assert_eq!((error.span().line, error.span().column), (3, 23));
+        assert_eq!(
+            error,
+            &ParseError::UnexpectedToken {
+                expected: Token::RightParen,
+                found: Token::Identifier("b".to_string()),
+                span: error.span(),
+            }
+        );
+    }
+
+    #[test]
+    fn diagnostics() {
+        let input = r#"
+            contract A is uint256 {}
+            contract B {
+                function f(uint[x] a) external;
+                function 1() external;
+                function g() external;
+            }
+        "#;
+        let mut parser = Parser::with_spans(Lexer::new(input).tokenize_spanned());
+        let unit = parser.parse_all_symbols();
+
+        let messages: Vec<String> = parser.diagnostics().iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "invalid base contract: uint256",
+                "invalid array length, found Identifier(\"x\")",
+                "expected identifier, found Number(\"1\")",
+            ]
+        );
+        assert_eq!(parser.diagnostics()[0].span().line, 2);
+        assert_eq!(parser.diagnostics()[0].span().column, 27);
+        assert_eq!(unit.contracts.len(), 1);
+        assert_eq!(unit.contracts[0].functions[0].signature(), "g()");
+    }
+
+    #[test]
+    fn file_level_constants() {
+        let input = r#"
+            uint256 constant MAX = type(uint256).max;
+            bytes32 constant ROLE = keccak256("ROLE");
+            type Price is uint128;
+            contract Market {
+                uint256 public constant LIMIT = type(uint128).max;
+                function quote(Price p) external {}
+            }
+        "#;
+        let mut parser = Parser::with_spans(Lexer::new(input).tokenize_spanned());
+        let unit = parser.parse_all_symbols();
+
+        assert!(
+            parser.diagnostics().is_empty(),
+            "{:?}",
+            parser.diagnostics()
+        );
+        let market = &unit.contracts[0];
+        assert_eq!(market.variables[0].signature(), "LIMIT()");
+        assert_eq!(market.functions[0].signature(), "quote(uint128)");
+    }
+
+    #[test]
+    fn special_functions() {
+        let input = r#"
+            contract Vault is Ownable {
+                constructor(address owner, uint256 cap) payable Ownable(owner) {}
+                fallback(bytes calldata input) external returns (bytes memory) {}
+                receive() external payable {}
+                function receiveAll() external {}
+            }
+
+            contract Legacy {
+                function Legacy(uint x) public {}
+                function() payable {}
+                function (uint) external callback;
+            }
+        "#;
+        let mut parser = Parser::new(Lexer::new(input).tokenize());
+        let unit = parser.parse_all_symbols();
+        assert!(parser.diagnostics().is_empty());
+
+        let vault = &unit.contracts[0];
+        let constructor = vault.special_function(SpecialKind::Constructor).unwrap();
+        let args: Vec<String> = constructor
+            .parameters
+            .iter()
+            .map(|p| p.type_name.canonical())
+            .collect();
+        assert_eq!(args, vec!["address", "uint256"]);
+        assert_eq!(constructor.mutability, Some(Mutability::Payable));
+        assert_eq!(
+            vault
+                .special_function(SpecialKind::Fallback)
+                .unwrap()
+                .parameters
+                .len(),
+            1
+        );
+        assert!(vault.special_function(SpecialKind::Receive).is_some());
+        let functions: Vec<String> = vault.functions.iter().map(|f| f.signature()).collect();
+        assert_eq!(functions, vec!["receiveAll()"]);
+
+        let legacy = &unit.contracts[1];
+        assert!(legacy.functions.is_empty());
+        assert_eq!(legacy.variables[0].name, "callback");
+        assert_eq!(
+            legacy
+                .special_function(SpecialKind::Constructor)
+                .unwrap()
+                .parameters
+                .len(),
+            1
+        );
+        assert_eq!(
+            legacy
+                .special_function(SpecialKind::Fallback)
+                .unwrap()
+                .mutability,
+            Some(Mutability::Payable)
+        );
+    }
 }
@@ -1,21 +1,52 @@
 use tiny_keccak::{Hasher, Keccak};
 
-pub fn compute_selector(signature: &str) -> [u8; 4] {
+// event topic0, the full keccak256 of the signature
+pub fn compute_topic(signature: &str) -> [u8; 32] {
     let mut hasher = Keccak::v256();
     let mut output = [0u8; 32];
 
     hasher.update(signature.as_bytes());
     hasher.finalize(&mut output);
 
+    output
+}
+
+pub fn compute_selector(signature: &str) -> [u8; 4] {
+    let hash = compute_topic(signature);
+
     let mut selector = [0u8; 4];
-    selector.copy_from_slice(&output[0..4]);
+    selector.copy_from_slice(&hash[0..4]);
     selector
 }
 
+// ERC-165: the XOR of every function selector an interface declares itself
+pub fn compute_interface_id<'a>(signatures: impl IntoIterator<Item = &'a str>) -> [u8; 4] {
+    signatures
+        .into_iter()
+        .map(compute_selector)
+        .fold([0u8; 4], |mut id, selector| {
+            for (byte, s) in id.iter_mut().zip(selector) {
+                *byte ^= s;
+            }
+            id
+        })
+}
+
 pub fn format_selector(selector: &[u8; 4]) -> String {
     format!("0x{}", hex::encode(selector))
 }
 
+pub fn format_topic(topic: &[u8; 32]) -> String {
+    format!("0x{}", hex::encode(topic))
+}
+
+// `0xA9059CBB` or `a9059cbb` -> `0xa9059cbb`; 4-byte selectors and 32-byte topics only
+pub fn normalize_selector(input: &str) -> Option<String> {
+    let digits = input.strip_prefix("0x").unwrap_or(input);
+    let valid = matches!(digits.len(), 8 | 64) && digits.chars().all(|c| c.is_ascii_hexdigit());
+    valid.then(|| format!("0x{}", digits.to_ascii_lowercase()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -40,4 +71,52 @@ mod tests {
         let selector = compute_selector(sig);
         assert_eq!(format_selector(&selector), "0x095ea7b3");
     }
+
+    #[test]
+    fn insufficient_balance_error() {
+        let sig = "InsufficientBalance(uint256,uint256)";
+        let selector = compute_selector(sig);
+        assert_eq!(format_selector(&selector), "0xcf479181");
+    }
+
+    #[test]
+    fn erc20_interface_id() {
+        let id = compute_interface_id([
+            "totalSupply()",
+            "balanceOf(address)",
+            "transfer(address,uint256)",
+            "allowance(address,address)",
+            "approve(address,uint256)",
+            "transferFrom(address,address,uint256)",
+        ]);
+        assert_eq!(format_selector(&id), "0x36372b07");
+
+        let erc165 = compute_interface_id(["supportsInterface(bytes4)"]);
+        assert_eq!(format_selector(&erc165), "0x01ffc9a7");
+    }
+
+    #[test]
+    fn normalize() {
+        assert_eq!(
+            normalize_selector("0xA9059CBB").as_deref(),
+            Some("0xa9059cbb")
+        );
+        assert_eq!(
+            normalize_selector("a9059cbb").as_deref(),
+            Some("0xa9059cbb")
+        );
+        assert!(normalize_selector(&format!("0x{}", "ab".repeat(32))).is_some());
+        assert_eq!(normalize_selector("0xa9059c"), None);
+        assert_eq!(normalize_selector("0xa9059cbz"), None);
+    }
+
+    #[test]
+    fn transfer_event() {
+        let sig = "Transfer(address,address,uint256)";
+        let topic = compute_topic(sig);
+        assert_eq!(
+            format_topic(&topic),
+            "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        );
+    }
 }
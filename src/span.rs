@@ -0,0 +1,65 @@
+// where a token or declaration starts in its source file, and how far it reaches
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    pub offset: usize, // bytes from the start of the file
+    pub len: usize,    // in bytes
+    pub line: usize,   // 1-based
+    pub column: usize, // 1-based, in characters
+}
+
+// rustc-style excerpt pointing at `span`:
+//
+//  --> src/Token.sol:12:5
+//    |
+// 12 |     function transfer(address to uint256 amount) external;
+//    |     ^^^^^^^^
+pub fn code_frame(source: &str, path: &str, span: Span) -> String {
+    let line = source
+        .lines()
+        .nth(span.line.saturating_sub(1))
+        .unwrap_or("");
+    let number = span.line.to_string();
+    let gutter = " ".repeat(number.len());
+
+    // markers cover the span on its first line, at least one of them
+    let start = span.column.saturating_sub(1);
+    let width = source
+        .get(span.offset..span.offset + span.len)
+        .and_then(|text| text.lines().next())
+        .map(|text| text.chars().count())
+        .unwrap_or(0)
+        .max(1);
+    let indent: String = line
+        .chars()
+        .take(start)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    format!(
+        "{gutter}--> {path}:{}:{}\n{gutter} |\n{number} | {line}\n{gutter} | {indent}{}",
+        span.line,
+        span.column,
+        "^".repeat(width)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame() {
+        let source = "contract A {\n\tfunction f(uint x y) external;\n}\n";
+        let span = Span {
+            offset: 32,
+            len: 1,
+            line: 2,
+            column: 20,
+        };
+
+        assert_eq!(
+            code_frame(source, "A.sol", span),
+            " --> A.sol:2:20\n  |\n2 | \tfunction f(uint x y) external;\n  | \t                  ^"
+        );
+    }
+}
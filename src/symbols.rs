@@ -0,0 +1,350 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{
+    Enum, Error, Event, Function, FunctionType, Parameter, SourceUnit, Struct, Type, UserValueType,
+    Variable,
+};
+
+// user-defined type declarations collected while parsing, used to turn
+// `Type::UserDefined` names into their ABI representation
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    structs: HashMap<String, Struct>,
+    enums: HashMap<String, Enum>,
+    value_types: HashMap<String, UserValueType>,
+    contracts: HashSet<String>,
+    bases: HashMap<String, Vec<String>>, // contract => bases, as written
+}
+
+// declarations are reachable both by their plain name and, when declared inside
+// a contract or library, by the qualified `Container.Name`
+fn insert_scoped<T: Clone>(map: &mut HashMap<String, T>, scope: Option<&str>, name: &str, def: T) {
+    if let Some(scope) = scope {
+        map.insert(format!("{}.{}", scope, name), def.clone());
+    }
+    map.entry(name.to_string()).or_insert(def);
+}
+
+fn qualified(scope: Option<&str>, name: &str) -> String {
+    match scope {
+        Some(scope) => format!("{}.{}", scope, name),
+        None => name.to_string(),
+    }
+}
+
+// names used inside a contract prefer that contract's own declarations, then those of its
+// bases, most derived first
+fn lookup_scoped<'a, T>(
+    map: &'a HashMap<String, T>,
+    scopes: &[String],
+    name: &str,
+) -> Option<&'a T> {
+    scopes
+        .iter()
+        .find_map(|scope| map.get(&format!("{}.{}", scope, name)))
+        .or_else(|| map.get(name))
+        .or_else(|| {
+            // `Lib.Order` declared somewhere we did not see the container of
+            let (_, last) = name.rsplit_once('.')?;
+            map.get(last)
+        })
+}
+
+// the contract a qualified declaration was made in, `Lib.Order` => `Lib`
+fn declaring_scope(name: &str) -> Option<&str> {
+    name.rsplit_once('.').map(|(scope, _)| scope)
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // structs and enums keep their fully qualified name, libraries' selectors use it
+    pub fn add_struct(&mut self, scope: Option<&str>, mut def: Struct) {
+        let name = def.name.clone();
+        def.name = qualified(scope, &name);
+        insert_scoped(&mut self.structs, scope, &name, def);
+    }
+
+    pub fn add_enum(&mut self, scope: Option<&str>, mut def: Enum) {
+        let name = def.name.clone();
+        def.name = qualified(scope, &name);
+        insert_scoped(&mut self.enums, scope, &name, def);
+    }
+
+    // qualified like structs, solc reports `Market.Qty` as the internalType
+    pub fn add_value_type(&mut self, scope: Option<&str>, mut def: UserValueType) {
+        let name = def.name.clone();
+        def.name = qualified(scope, &name);
+        insert_scoped(&mut self.value_types, scope, &name, def);
+    }
+
+    pub fn add_contract(&mut self, name: &str) {
+        self.contracts.insert(name.to_string());
+    }
+
+    pub fn add_bases(&mut self, name: &str, bases: &[String]) {
+        self.bases.insert(name.to_string(), bases.to_vec());
+    }
+
+    // `scope` followed by the contracts it inherits from, so inherited declarations can be
+    // used unqualified
+    fn scopes(&self, scope: Option<&str>) -> Vec<String> {
+        let mut scopes: Vec<String> = scope.map(str::to_string).into_iter().collect();
+        let mut i = 0;
+        while i < scopes.len() {
+            // solidity lists bases from most base-like to most derived
+            for base in self.bases.get(&scopes[i]).into_iter().flatten().rev() {
+                if !scopes.contains(base) {
+                    scopes.push(base.clone());
+                }
+            }
+            i += 1;
+        }
+        scopes
+    }
+
+    // everything `other` knows about, without replacing local declarations
+    pub fn merge(&mut self, other: &SymbolTable) {
+        self.merge_renamed(other, |key| Some(key.to_string()));
+    }
+
+    // import "./A.sol" as A; every declaration becomes reachable as `A.Name`
+    pub fn merge_aliased(&mut self, other: &SymbolTable, alias: &str) {
+        self.merge_renamed(other, |key| Some(format!("{}.{}", alias, key)));
+    }
+
+    // import {Name as Alias} from "./A.sol"; including declarations qualified by it,
+    // so importing a library also brings in `Lib.Order`
+    pub fn merge_symbol(&mut self, other: &SymbolTable, name: &str, alias: &str) {
+        let prefix = format!("{}.", name);
+        self.merge_renamed(other, |key| {
+            if key == name {
+                Some(alias.to_string())
+            } else {
+                key.strip_prefix(&prefix)
+                    .map(|rest| format!("{}.{}", alias, rest))
+            }
+        });
+    }
+
+    // resolves struct members in place, so a struct keeps the meaning its members had in the
+    // file that declared it once it is imported somewhere those names are not visible
+    pub fn resolve_declarations(&mut self) {
+        let resolved: Vec<(String, Struct)> = self
+            .structs
+            .iter()
+            .map(|(key, def)| {
+                let scope = declaring_scope(&def.name);
+                let mut stack = vec![def.name.clone()];
+                let members = def
+                    .members
+                    .iter()
+                    .map(|m| self.resolve_parameter_inner(scope, m, &mut stack))
+                    .collect();
+                (
+                    key.clone(),
+                    Struct {
+                        name: def.name.clone(),
+                        members,
+                    },
+                )
+            })
+            .collect();
+
+        self.structs.extend(resolved);
+    }
+
+    fn merge_renamed(&mut self, other: &SymbolTable, rename: impl Fn(&str) -> Option<String>) {
+        for (key, def) in &other.structs {
+            if let Some(key) = rename(key) {
+                self.structs.entry(key).or_insert_with(|| def.clone());
+            }
+        }
+        for (key, def) in &other.enums {
+            if let Some(key) = rename(key) {
+                self.enums.entry(key).or_insert_with(|| def.clone());
+            }
+        }
+        for (key, def) in &other.value_types {
+            if let Some(key) = rename(key) {
+                self.value_types.entry(key).or_insert_with(|| def.clone());
+            }
+        }
+        for key in &other.contracts {
+            if let Some(key) = rename(key) {
+                self.contracts.insert(key);
+            }
+        }
+        for (key, bases) in &other.bases {
+            if let Some(key) = rename(key) {
+                self.bases.entry(key).or_insert_with(|| bases.clone());
+            }
+        }
+    }
+
+    // `scope` is the contract the type is written in, if any
+    pub fn resolve_type(&self, scope: Option<&str>, t: &Type) -> Type {
+        self.resolve_inner(scope, t, &mut Vec::new())
+    }
+
+    fn resolve_inner(&self, scope: Option<&str>, t: &Type, stack: &mut Vec<String>) -> Type {
+        match t {
+            Type::Array(inner) => Type::Array(Box::new(self.resolve_inner(scope, inner, stack))),
+            Type::FixedArray(inner, size) => {
+                Type::FixedArray(Box::new(self.resolve_inner(scope, inner, stack)), *size)
+            }
+            Type::Mapping(key, value) => Type::Mapping(
+                Box::new(self.resolve_inner(scope, key, stack)),
+                Box::new(self.resolve_inner(scope, value, stack)),
+            ),
+            Type::UserDefined(name) => self
+                .resolve_name(scope, name, stack)
+                .unwrap_or_else(|| t.clone()),
+            // already resolved, but members may name types from files parsed later
+            Type::Struct(name, members) => {
+                stack.push(name.clone());
+                let members = members
+                    .iter()
+                    .map(|m| self.resolve_parameter_inner(declaring_scope(name), m, stack))
+                    .collect();
+                stack.pop();
+                Type::Struct(name.clone(), members)
+            }
+            Type::Function(func) => Type::Function(FunctionType {
+                parameters: func
+                    .parameters
+                    .iter()
+                    .map(|p| self.resolve_parameter_inner(scope, p, stack))
+                    .collect(),
+                returns: func
+                    .returns
+                    .iter()
+                    .map(|p| self.resolve_parameter_inner(scope, p, stack))
+                    .collect(),
+                ..func.clone()
+            }),
+            _ => t.clone(),
+        }
+    }
+
+    fn resolve_name(
+        &self,
+        scope: Option<&str>,
+        name: &str,
+        stack: &mut Vec<String>,
+    ) -> Option<Type> {
+        let scopes = self.scopes(scope);
+        if let Some(def) = lookup_scoped(&self.structs, &scopes, name) {
+            // recursive structs cannot appear in an ABI, leave them unresolved
+            if stack.contains(&def.name) {
+                return None;
+            }
+
+            // members mean what they did where the struct was declared
+            stack.push(def.name.clone());
+            let members = def
+                .members
+                .iter()
+                .map(|m| self.resolve_parameter_inner(declaring_scope(&def.name), m, stack))
+                .collect();
+            stack.pop();
+
+            return Some(Type::Struct(def.name.clone(), members));
+        }
+
+        if let Some(def) = lookup_scoped(&self.enums, &scopes, name) {
+            return Some(Type::Enum(def.name.clone()));
+        }
+
+        if let Some(def) = lookup_scoped(&self.value_types, &scopes, name) {
+            return Some(Type::UserValue(
+                def.name.clone(),
+                Box::new(def.underlying.clone()),
+            ));
+        }
+
+        if self.contracts.contains(name) {
+            return Some(Type::Contract(name.to_string()));
+        }
+
+        None
+    }
+
+    fn resolve_parameter_inner(
+        &self,
+        scope: Option<&str>,
+        param: &Parameter,
+        stack: &mut Vec<String>,
+    ) -> Parameter {
+        Parameter {
+            type_name: self.resolve_inner(scope, &param.type_name, stack),
+            ..param.clone()
+        }
+    }
+
+    pub fn resolve_parameter(&self, scope: Option<&str>, param: &Parameter) -> Parameter {
+        self.resolve_parameter_inner(scope, param, &mut Vec::new())
+    }
+
+    pub fn resolve_function(&self, scope: Option<&str>, func: &mut Function) {
+        for param in func.parameters.iter_mut() {
+            *param = self.resolve_parameter(scope, param);
+        }
+        if let Some(returns) = func.returns.as_mut() {
+            for param in returns.iter_mut() {
+                *param = self.resolve_parameter(scope, param);
+            }
+        }
+    }
+
+    pub fn resolve_event(&self, scope: Option<&str>, event: &mut Event) {
+        for param in event.parameters.iter_mut() {
+            param.type_name = self.resolve_type(scope, &param.type_name);
+        }
+    }
+
+    pub fn resolve_error(&self, scope: Option<&str>, error: &mut Error) {
+        for param in error.parameters.iter_mut() {
+            *param = self.resolve_parameter(scope, param);
+        }
+    }
+
+    pub fn resolve_variable(&self, scope: Option<&str>, var: &mut Variable) {
+        var.type_name = self.resolve_type(scope, &var.type_name);
+    }
+
+    pub fn resolve_source_unit(&self, unit: &mut SourceUnit) {
+        for contract in unit.contracts.iter_mut() {
+            let scope = Some(contract.name.as_str());
+            for func in contract.functions.iter_mut() {
+                self.resolve_function(scope, func);
+            }
+            for var in contract.variables.iter_mut() {
+                self.resolve_variable(scope, var);
+            }
+            for event in contract.events.iter_mut() {
+                self.resolve_event(scope, event);
+            }
+            for error in contract.errors.iter_mut() {
+                self.resolve_error(scope, error);
+            }
+            for special in contract.special_functions.iter_mut() {
+                for param in special.parameters.iter_mut() {
+                    *param = self.resolve_parameter(scope, param);
+                }
+            }
+        }
+
+        for func in unit.functions.iter_mut() {
+            self.resolve_function(None, func);
+        }
+        for event in unit.events.iter_mut() {
+            self.resolve_event(None, event);
+        }
+        for error in unit.errors.iter_mut() {
+            self.resolve_error(None, error);
+        }
+    }
+}
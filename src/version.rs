@@ -0,0 +1,223 @@
+// compiler versions as written in `pragma solidity`, npm semver style
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Comparator {
+    op: Op,
+    version: Version,
+}
+
+impl Comparator {
+    fn matches(&self, v: &Version) -> bool {
+        match self.op {
+            Op::Exact => *v == self.version,
+            Op::Greater => *v > self.version,
+            Op::GreaterEq => *v >= self.version,
+            Op::Less => *v < self.version,
+            Op::LessEq => *v <= self.version,
+        }
+    }
+}
+
+// `^0.8.0`, `>=0.4.22 <0.9.0`, `0.8.19 || ^0.7`; alternatives of comparators that must all hold
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionReq {
+    alternatives: Vec<Vec<Comparator>>,
+}
+
+impl VersionReq {
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut alternatives = Vec::new();
+
+        for alternative in input.split("||") {
+            let mut comparators = Vec::new();
+            let mut words = alternative.split_whitespace().peekable();
+
+            while let Some(word) = words.next() {
+                let split = word
+                    .find(|c: char| !matches!(c, '^' | '~' | '>' | '<' | '='))
+                    .unwrap_or(word.len());
+                let (op, version) = word.split_at(split);
+
+                // `>= 0.4.22` with a space between operator and version
+                let version = if version.is_empty() {
+                    words.next()?
+                } else {
+                    version
+                };
+
+                comparators.extend(expand(op, version)?);
+            }
+
+            if comparators.is_empty() {
+                return None;
+            }
+            alternatives.push(comparators);
+        }
+
+        Some(VersionReq { alternatives })
+    }
+
+    pub fn matches(&self, v: &Version) -> bool {
+        self.alternatives
+            .iter()
+            .any(|comparators| comparators.iter().all(|c| c.matches(v)))
+    }
+
+    // whether any 0.4.x release satisfies the requirement; those compilers still
+    // defaulted functions without a visibility to public
+    pub fn allows_pre_050(&self) -> bool {
+        (0..=26).any(|patch| self.matches(&Version::new(0, 4, patch)))
+    }
+}
+
+// `0.8`, `0.8.x` and `0.8.*` leave the patch open
+fn parse_partial(version: &str) -> Option<(Version, usize)> {
+    let mut parts = [0; 3];
+    let mut given = 0;
+
+    for (i, part) in version.split('.').enumerate() {
+        if i >= 3 {
+            return None;
+        }
+        if matches!(part, "x" | "X" | "*") {
+            break;
+        }
+        parts[i] = part.parse().ok()?;
+        given = i + 1;
+    }
+
+    Some((Version::new(parts[0], parts[1], parts[2]), given))
+}
+
+// the bump that ends the range of a partial version: 0.8 -> 0.9.0, 1 -> 2.0.0
+fn next_after(version: Version, given: usize) -> Version {
+    match given {
+        0 => Version::new(u64::MAX, 0, 0),
+        1 => Version::new(version.major + 1, 0, 0),
+        2 => Version::new(version.major, version.minor + 1, 0),
+        _ => Version::new(version.major, version.minor, version.patch + 1),
+    }
+}
+
+fn expand(op: &str, version: &str) -> Option<Vec<Comparator>> {
+    let (v, given) = parse_partial(version)?;
+    let lower = |op| Comparator { op, version: v };
+
+    let comparators = match op {
+        "" | "=" if given == 3 => vec![lower(Op::Exact)],
+        "" | "=" => vec![
+            lower(Op::GreaterEq),
+            Comparator {
+                op: Op::Less,
+                version: next_after(v, given),
+            },
+        ],
+        // ^ keeps the left-most non-zero component fixed
+        "^" => {
+            let upper = if v.major > 0 || given == 1 {
+                Version::new(v.major + 1, 0, 0)
+            } else if v.minor > 0 || given == 2 {
+                Version::new(0, v.minor + 1, 0)
+            } else {
+                Version::new(0, 0, v.patch + 1)
+            };
+            vec![
+                lower(Op::GreaterEq),
+                Comparator {
+                    op: Op::Less,
+                    version: upper,
+                },
+            ]
+        }
+        // ~ allows patch level changes, or minor ones when only the major is given
+        "~" => vec![
+            lower(Op::GreaterEq),
+            Comparator {
+                op: Op::Less,
+                version: next_after(v, given.min(2)),
+            },
+        ],
+        ">" => vec![lower(Op::Greater)],
+        ">=" => vec![lower(Op::GreaterEq)],
+        "<" => vec![lower(Op::Less)],
+        "<=" => vec![lower(Op::LessEq)],
+        _ => return None,
+    };
+
+    Some(comparators)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req(input: &str) -> VersionReq {
+        VersionReq::parse(input).unwrap()
+    }
+
+    #[test]
+    fn caret_and_tilde() {
+        let caret = req("^0.8.13");
+        assert!(caret.matches(&Version::new(0, 8, 13)));
+        assert!(caret.matches(&Version::new(0, 8, 26)));
+        assert!(!caret.matches(&Version::new(0, 8, 12)));
+        assert!(!caret.matches(&Version::new(0, 9, 0)));
+
+        let tilde = req("~0.4.11");
+        assert!(tilde.matches(&Version::new(0, 4, 26)));
+        assert!(!tilde.matches(&Version::new(0, 5, 0)));
+    }
+
+    #[test]
+    fn ranges() {
+        let range = req(">= 0.4.22 <0.9.0");
+        assert!(range.matches(&Version::new(0, 4, 22)));
+        assert!(range.matches(&Version::new(0, 8, 30)));
+        assert!(!range.matches(&Version::new(0, 9, 0)));
+
+        let either = req("0.8.19 || ^0.7");
+        assert!(either.matches(&Version::new(0, 8, 19)));
+        assert!(either.matches(&Version::new(0, 7, 6)));
+        assert!(!either.matches(&Version::new(0, 8, 20)));
+
+        let partial = req("0.4.x");
+        assert!(partial.matches(&Version::new(0, 4, 0)));
+        assert!(!partial.matches(&Version::new(0, 5, 0)));
+
+        assert_eq!(VersionReq::parse("latest"), None);
+    }
+
+    #[test]
+    fn pre_050() {
+        assert!(req("^0.4.24").allows_pre_050());
+        assert!(req(">=0.4.22 <0.9.0").allows_pre_050());
+        assert!(!req("^0.5.0").allows_pre_050());
+        assert!(!req("^0.8.26").allows_pre_050());
+    }
+}
@@ -0,0 +1,937 @@
+// vyper front-end: turns .vy modules and .vyi interface files into the same ast as
+// solidity sources, so both end up in the same rows
+use std::collections::HashSet;
+
+use crate::ast::{
+    ContractDefinition, ContractKind, Event, EventParameter, Function, Mutability, Parameter,
+    SourceUnit, SpecialFunction, SpecialKind, Struct, Type, Variable, Visibility,
+    is_valid_elementary,
+};
+use crate::parser::ParseError;
+use crate::span::Span;
+use crate::symbols::SymbolTable;
+use crate::version::{Version, VersionReq};
+
+// one statement, possibly continued over several physical lines inside brackets
+#[derive(Debug)]
+struct Line {
+    text: String,
+    indent: usize,
+    span: Span, // of the first non-blank character
+}
+
+pub struct VyperParser {
+    lines: Vec<Line>,
+    position: usize,
+    version: Option<VersionReq>,
+    flags: HashSet<String>,
+    symbols: SymbolTable,
+    diagnostics: Vec<ParseError>,
+}
+
+impl VyperParser {
+    pub fn new(source: &str) -> Self {
+        let (lines, version) = logical_lines(source);
+
+        VyperParser {
+            lines,
+            position: 0,
+            version,
+            flags: HashSet::new(),
+            symbols: SymbolTable::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    pub fn diagnostics(&self) -> &[ParseError] {
+        &self.diagnostics
+    }
+
+    // a module is one contract named after its file; a .vyi file is one interface
+    pub fn parse_module(&mut self, name: &str, interface_file: bool) -> SourceUnit {
+        // flags are plain uint256 in the ABI and may be used before they are declared
+        for line in &self.lines {
+            if line.indent == 0
+                && let Some(rest) =
+                    strip_keyword(&line.text, "flag").or_else(|| strip_keyword(&line.text, "enum"))
+            {
+                self.flags
+                    .insert(rest.trim_end_matches(':').trim().to_string());
+            }
+        }
+
+        let mut contract = ContractDefinition {
+            kind: if interface_file {
+                ContractKind::Interface
+            } else {
+                ContractKind::Contract
+            },
+            name: name.to_string(),
+            bases: Vec::new(),
+            functions: Vec::new(),
+            variables: Vec::new(),
+            events: Vec::new(),
+            errors: Vec::new(),
+            special_functions: Vec::new(),
+            span: Span {
+                offset: 0,
+                len: 0,
+                line: 1,
+                column: 1,
+            },
+        };
+        let mut interfaces = Vec::new();
+        let mut decorators = Vec::new();
+
+        while self.position < self.lines.len() {
+            let line = &self.lines[self.position];
+            let span = line.span;
+            let text = line.text.clone();
+            self.position += 1;
+
+            let result = if let Some(decorator) = text.strip_prefix('@') {
+                decorators.push(decorator.split('(').next().unwrap_or("").trim().to_string());
+                continue;
+            } else if text.starts_with("def ") {
+                let decorators = std::mem::take(&mut decorators);
+                self.skip_block(0);
+                self.parse_def(&text, span, &decorators, interface_file, &mut contract)
+            } else if let Some(rest) = strip_keyword(&text, "event") {
+                self.parse_event(rest, span)
+                    .map(|event| contract.events.push(event))
+            } else if let Some(rest) = strip_keyword(&text, "struct") {
+                self.parse_struct(rest)
+            } else if let Some(rest) = strip_keyword(&text, "interface") {
+                self.parse_interface(rest, span)
+                    .map(|interface| interfaces.push(interface))
+            } else if strip_keyword(&text, "flag").is_some()
+                || strip_keyword(&text, "enum").is_some()
+            {
+                self.skip_block(0);
+                Ok(())
+            } else if text.starts_with("import ") || text.starts_with("from ") {
+                self.add_imported_names(&text);
+                Ok(())
+            } else if let Some((name, rest)) = text.split_once(':')
+                && is_identifier(name.trim())
+                && !matches!(
+                    name.trim(),
+                    "implements" | "uses" | "initializes" | "exports"
+                )
+            {
+                self.parse_variable(name.trim(), rest, span)
+                    .map(|var| contract.variables.extend(var))
+            } else {
+                // implements:, exports:, docstrings and the like carry no selectors
+                self.skip_block(0);
+                Ok(())
+            };
+
+            if let Err(error) = result {
+                self.diagnostics.push(error);
+            }
+        }
+
+        let mut unit = SourceUnit {
+            solidity: None,
+            imports: Vec::new(),
+            contracts: interfaces,
+            functions: Vec::new(),
+            events: Vec::new(),
+            errors: Vec::new(),
+        };
+        unit.contracts.insert(0, contract);
+        self.symbols.resolve_source_unit(&mut unit);
+
+        unit
+    }
+
+    // skips the lines indented deeper than `indent`
+    fn skip_block(&mut self, indent: usize) {
+        while self.position < self.lines.len() && self.lines[self.position].indent > indent {
+            self.position += 1;
+        }
+    }
+
+    // the lines indented deeper than `indent`, consumed
+    fn block(&mut self, indent: usize) -> Vec<(String, Span)> {
+        let mut body = Vec::new();
+        while self.position < self.lines.len() && self.lines[self.position].indent > indent {
+            let line = &self.lines[self.position];
+            body.push((line.text.clone(), line.span));
+            self.position += 1;
+        }
+        body
+    }
+
+    // @external @view def name(a: uint256, b: address = empty(address)) -> bool:
+    fn parse_def(
+        &mut self,
+        text: &str,
+        span: Span,
+        decorators: &[String],
+        interface_file: bool,
+        contract: &mut ContractDefinition,
+    ) -> Result<(), ParseError> {
+        let header = self.parse_header(text, span)?;
+
+        let mut visibility = Visibility::Internal;
+        let mut mutability = None;
+        for decorator in decorators {
+            match decorator.as_str() {
+                "external" => visibility = Visibility::External,
+                "view" => mutability = Some(Mutability::View),
+                "pure" => mutability = Some(Mutability::Pure),
+                "payable" => mutability = Some(Mutability::Payable),
+                _ => {}
+            }
+        }
+        // interface files only declare what is callable from outside
+        if interface_file {
+            visibility = Visibility::External;
+        }
+
+        let span = header.span;
+        match header.name.as_str() {
+            "__init__" => contract.special_functions.push(SpecialFunction {
+                kind: SpecialKind::Constructor,
+                parameters: header.parameters,
+                mutability,
+                span,
+            }),
+            // vyper has no receive, a payable __default__ takes plain transfers as well
+            "__default__" => contract.special_functions.push(SpecialFunction {
+                kind: SpecialKind::Fallback,
+                parameters: Vec::new(),
+                mutability,
+                span,
+            }),
+            _ => contract
+                .functions
+                .extend(header.overloads(visibility, mutability)),
+        }
+
+        Ok(())
+    }
+
+    fn parse_header(&self, text: &str, span: Span) -> Result<Header, ParseError> {
+        let rest = text.strip_prefix("def ").unwrap_or(text);
+        let open = rest.find('(').ok_or_else(|| syntax("expected (", span))?;
+        let close = matching(rest, open).ok_or_else(|| syntax("unclosed (", span))?;
+        let name = rest[..open].trim().to_string();
+        if !is_identifier(&name) {
+            return Err(syntax(format!("invalid function name {}", name), span));
+        }
+
+        let mut parameters = Vec::new();
+        let mut defaults = 0;
+        for arg in split_top_level(&rest[open + 1..close], ',') {
+            let (arg_name, type_text) = arg
+                .split_once(':')
+                .ok_or_else(|| syntax(format!("expected a typed argument, found {}", arg), span))?;
+            let type_text = match type_text.split_once('=') {
+                Some((type_text, _)) => {
+                    defaults += 1;
+                    type_text
+                }
+                None => type_text,
+            };
+            parameters.push(Parameter {
+                type_name: self.parse_type(type_text, span)?,
+                name: Some(arg_name.trim().to_string()),
+                data_location: None,
+            });
+        }
+
+        // -> uint256: or -> (uint256, bool): followed by a body or, in interfaces, a mutability
+        let after = rest[close + 1..].trim();
+        let (returns, tail) = match after.strip_prefix("->") {
+            Some(returns_text) => {
+                let colon = find_top_level(returns_text, ':')
+                    .ok_or_else(|| syntax("expected : after the return type", span))?;
+                let types = returns_text[..colon].trim();
+                let types = match types.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+                    Some(inner) => split_top_level(inner, ','),
+                    None => vec![types],
+                };
+                let returns = types
+                    .into_iter()
+                    .map(|t| {
+                        Ok(Parameter {
+                            type_name: self.parse_type(t, span)?,
+                            name: None,
+                            data_location: None,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, ParseError>>()?;
+                (returns, &returns_text[colon + 1..])
+            }
+            None => (Vec::new(), after.strip_prefix(':').unwrap_or(after)),
+        };
+
+        let offset = text.find(&name).unwrap_or(0);
+        Ok(Header {
+            name,
+            parameters,
+            defaults,
+            returns,
+            mutability: match tail.trim() {
+                "view" => Some(Mutability::View),
+                "pure" => Some(Mutability::Pure),
+                "payable" => Some(Mutability::Payable),
+                _ => None,
+            },
+            span: Span {
+                offset: span.offset + offset,
+                len: 0,
+                line: span.line,
+                column: span.column + offset,
+            },
+        })
+    }
+
+    // event Transfer:
+    //     sender: indexed(address)
+    //     value: uint256
+    fn parse_event(&mut self, rest: &str, span: Span) -> Result<Event, ParseError> {
+        let name = rest.trim_end_matches(':').trim().to_string();
+        let mut parameters = Vec::new();
+
+        for (field, field_span) in self.block(0) {
+            if field == "pass" {
+                continue;
+            }
+            let (field_name, type_text) = field.split_once(':').ok_or_else(|| {
+                syntax(
+                    format!("expected an event field, found {}", field),
+                    field_span,
+                )
+            })?;
+            let type_text = type_text.trim();
+            let (type_text, indexed) = match unwrap_call(type_text, "indexed") {
+                Some(inner) => (inner, true),
+                None => (type_text, false),
+            };
+            parameters.push(EventParameter {
+                type_name: self.parse_type(type_text, field_span)?,
+                name: Some(field_name.trim().to_string()),
+                indexed,
+            });
+        }
+
+        Ok(Event {
+            name,
+            parameters,
+            anonymous: false,
+            span: keyword_span(span, "event "),
+        })
+    }
+
+    fn parse_struct(&mut self, rest: &str) -> Result<(), ParseError> {
+        let name = rest.trim_end_matches(':').trim().to_string();
+        let mut members = Vec::new();
+
+        for (field, field_span) in self.block(0) {
+            let (field_name, type_text) = field.split_once(':').ok_or_else(|| {
+                syntax(
+                    format!("expected a struct member, found {}", field),
+                    field_span,
+                )
+            })?;
+            members.push(Parameter {
+                type_name: self.parse_type(type_text, field_span)?,
+                name: Some(field_name.trim().to_string()),
+                data_location: None,
+            });
+        }
+
+        self.symbols.add_struct(None, Struct { name, members });
+        Ok(())
+    }
+
+    // interface Token:
+    //     def transfer(to: address, amount: uint256) -> bool: nonpayable
+    fn parse_interface(
+        &mut self,
+        rest: &str,
+        span: Span,
+    ) -> Result<ContractDefinition, ParseError> {
+        let name = rest.trim_end_matches(':').trim().to_string();
+        self.symbols.add_contract(&name);
+
+        let mut functions = Vec::new();
+        for (line, line_span) in self.block(0) {
+            if line == "pass" {
+                continue;
+            }
+            let header = self.parse_header(&line, line_span)?;
+            let mutability = header.mutability.clone();
+            functions.extend(header.overloads(Visibility::External, mutability));
+        }
+
+        Ok(ContractDefinition {
+            kind: ContractKind::Interface,
+            name,
+            bases: Vec::new(),
+            functions,
+            variables: Vec::new(),
+            events: Vec::new(),
+            errors: Vec::new(),
+            special_functions: Vec::new(),
+            span: keyword_span(span, "interface "),
+        })
+    }
+
+    // balances: public(HashMap[address, uint256]); only public ones have getters
+    fn parse_variable(
+        &mut self,
+        name: &str,
+        rest: &str,
+        span: Span,
+    ) -> Result<Option<Variable>, ParseError> {
+        let type_text = match find_top_level(rest, '=') {
+            Some(eq) => &rest[..eq],
+            None => rest,
+        }
+        .trim();
+
+        let (type_text, visibility) = match unwrap_call(type_text, "public") {
+            Some(inner) => (inner, Visibility::Public),
+            None => (type_text, Visibility::Internal),
+        };
+        let type_text = ["constant", "immutable", "transient"]
+            .iter()
+            .find_map(|wrapper| unwrap_call(type_text, wrapper))
+            .unwrap_or(type_text);
+
+        if visibility != Visibility::Public {
+            return Ok(None);
+        }
+
+        Ok(Some(Variable {
+            type_name: self.parse_type(type_text, span)?,
+            name: name.to_string(),
+            visibility,
+            overrides: None,
+            span,
+        }))
+    }
+
+    // from ethereum.ercs import IERC20; import interfaces.Token as Token
+    // imported names can only be used as interface types
+    fn add_imported_names(&mut self, text: &str) {
+        let names = match text.split_once(" import ") {
+            Some((_, names)) => names,
+            None => text.trim_start_matches("import "),
+        };
+        for name in split_top_level(names.trim_matches(|c| c == '(' || c == ')'), ',') {
+            let name = match name.split_once(" as ") {
+                Some((_, alias)) => alias,
+                None => name.rsplit('.').next().unwrap_or(name),
+            };
+            self.symbols.add_contract(name.trim());
+        }
+    }
+
+    fn parse_type(&self, text: &str, span: Span) -> Result<Type, ParseError> {
+        let text = text.trim();
+
+        if let Some(open) = trailing_bracket(text) {
+            let base = text[..open].trim();
+            let inner = &text[open + 1..text.len() - 1];
+            let args = split_top_level(inner, ',');
+
+            return match (base, args.as_slice()) {
+                ("HashMap", [key, value]) => Ok(Type::Mapping(
+                    Box::new(self.parse_type(key, span)?),
+                    Box::new(self.parse_type(value, span)?),
+                )),
+                ("DynArray", [element, _]) => {
+                    Ok(Type::Array(Box::new(self.parse_type(element, span)?)))
+                }
+                ("String", [_]) => Ok(Type::Elementary("string".to_string())),
+                ("Bytes", [_]) => Ok(Type::Elementary("bytes".to_string())),
+                (_, [size]) => {
+                    let size = size
+                        .parse()
+                        .map_err(|_| syntax(format!("invalid array length in {}", text), span))?;
+                    Ok(Type::FixedArray(
+                        Box::new(self.parse_type(base, span)?),
+                        size,
+                    ))
+                }
+                _ => Err(syntax(format!("invalid type {}", text), span)),
+            };
+        }
+
+        match text {
+            // vyper 0.4 encodes decimals as int168, earlier versions as fixed168x10
+            "decimal" => Ok(Type::Elementary(self.decimal_type().to_string())),
+            "bytes" | "string" | "byte" | "uint" | "int" | "fixed" | "ufixed" => {
+                Err(syntax(format!("invalid type {}", text), span))
+            }
+            _ if is_valid_elementary(text) => Ok(Type::Elementary(text.to_string())),
+            _ if self.flags.contains(text) => Ok(Type::Elementary("uint256".to_string())),
+            _ if is_identifier(text.replace('.', "_").as_str()) => {
+                Ok(Type::UserDefined(text.to_string()))
+            }
+            _ => Err(syntax(format!("invalid type {}", text), span)),
+        }
+    }
+
+    fn decimal_type(&self) -> &'static str {
+        match &self.version {
+            Some(req) if !(0..=10).any(|patch| req.matches(&Version::new(0, 3, patch))) => "int168",
+            _ => "fixed168x10",
+        }
+    }
+}
+
+struct Header {
+    name: String,
+    parameters: Vec<Parameter>,
+    defaults: usize, // trailing arguments with a default value
+    returns: Vec<Parameter>,
+    mutability: Option<Mutability>, // as written after an interface function
+    span: Span,
+}
+
+impl Header {
+    // vyper exposes one entry point per number of default arguments given
+    fn overloads(&self, visibility: Visibility, mutability: Option<Mutability>) -> Vec<Function> {
+        let required = self.parameters.len() - self.defaults;
+
+        (required..=self.parameters.len())
+            .map(|count| Function {
+                name: self.name.clone(),
+                parameters: self.parameters[..count].to_vec(),
+                visibility: visibility.clone(),
+                mutability: mutability.clone(),
+                is_virtual: false,
+                overrides: None,
+                modifiers: Vec::new(),
+                returns: Some(self.returns.clone()),
+                span: self.span,
+            })
+            .collect()
+    }
+}
+
+fn syntax(message: impl Into<String>, span: Span) -> ParseError {
+    ParseError::Syntax {
+        message: message.into(),
+        span,
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+// `event Transfer:` -> `Transfer:`
+fn strip_keyword<'a>(text: &'a str, keyword: &str) -> Option<&'a str> {
+    text.strip_prefix(keyword)?.strip_prefix(' ')
+}
+
+// the span of the name following `keyword`
+fn keyword_span(span: Span, keyword: &str) -> Span {
+    Span {
+        offset: span.offset + keyword.len(),
+        column: span.column + keyword.len(),
+        ..span
+    }
+}
+
+// `public(uint256)` -> `uint256`
+fn unwrap_call<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let inner = text.strip_prefix(name)?.trim_start().strip_prefix('(')?;
+    let close = matching(text, text.len() - inner.len() - 1)?;
+    (close == text.len() - 1).then(|| inner[..inner.len() - 1].trim())
+}
+
+// index of the bracket closing the one at `open`
+fn matching(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices().skip_while(|(i, _)| *i < open) {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// index of the `[` opening the `]` that ends `text`, as in `uint256[3]` or `HashMap[K, V]`
+fn trailing_bracket(text: &str) -> Option<usize> {
+    if !text.ends_with(']') {
+        return None;
+    }
+    let mut depth = 0;
+    for (i, c) in text.char_indices().rev() {
+        match c {
+            ']' | ')' => depth += 1,
+            '[' | '(' => {
+                depth -= 1;
+                if depth == 0 {
+                    return (i > 0).then_some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn find_top_level(text: &str, needle: char) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            _ if c == needle && depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(i) = find_top_level(rest, separator) {
+        parts.push(rest[..i].trim());
+        rest = &rest[i + separator.len_utf8()..];
+    }
+    if !rest.trim().is_empty() {
+        parts.push(rest.trim());
+    }
+    parts
+}
+
+// joins physical lines continued inside brackets, drops comments, blank lines and
+// docstrings, and picks up the `# pragma version` / `# @version` comment
+fn logical_lines(source: &str) -> (Vec<Line>, Option<VersionReq>) {
+    let mut lines = Vec::new();
+    let mut version = None;
+
+    let mut text = String::new();
+    let mut start: Option<Span> = None;
+    let mut indent = 0;
+    let mut depth = 0i32;
+    let mut quote: Option<&str> = None;
+
+    let mut line = 1;
+    let mut column = 1;
+    let mut at_line_start = true;
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        let here = Span {
+            offset,
+            len: 0,
+            line,
+            column,
+        };
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+
+        if let Some(q) = quote {
+            // strings are kept in the text so `#` inside them is no comment
+            text.push(c);
+            if source[offset..].starts_with(q) {
+                for _ in 1..q.len() {
+                    if let Some((_, c)) = chars.next() {
+                        text.push(c);
+                        column += 1;
+                    }
+                }
+                quote = None;
+            } else if c == '\\'
+                && let Some((_, escaped)) = chars.next()
+            {
+                text.push(escaped);
+                column += 1;
+            }
+            continue;
+        }
+
+        match c {
+            '#' => {
+                let end = source[offset..]
+                    .find('\n')
+                    .map_or(source.len(), |e| offset + e);
+                let comment = source[offset + 1..end].trim();
+                if let Some(req) = comment
+                    .strip_prefix("@version")
+                    .or_else(|| comment.strip_prefix("pragma version"))
+                {
+                    // pep 440 operators, as far as they differ from npm's
+                    version = VersionReq::parse(&req.trim().replace("~=", "~").replace("==", "="));
+                }
+                while chars.peek().is_some_and(|(i, _)| *i < end) {
+                    chars.next();
+                    column += 1;
+                }
+            }
+            '\n' => {
+                if depth == 0 {
+                    push_line(&mut lines, &mut text, &mut start, indent);
+                    at_line_start = true;
+                    indent = 0;
+                } else {
+                    text.push(' ');
+                }
+            }
+            ' ' | '\t' | '\r' if at_line_start => indent += 1,
+            _ => {
+                at_line_start = false;
+                if start.is_none() {
+                    start = Some(here);
+                }
+                match c {
+                    '(' | '[' | '{' => depth += 1,
+                    ')' | ']' | '}' => depth -= 1,
+                    '"' | '\'' => {
+                        let triple = if c == '"' { "\"\"\"" } else { "'''" };
+                        if source[offset..].starts_with(triple) {
+                            text.push_str(triple);
+                            chars.next();
+                            chars.next();
+                            column += 2;
+                            quote = Some(triple);
+                            continue;
+                        }
+                        quote = Some(if c == '"' { "\"" } else { "'" });
+                    }
+                    _ => {}
+                }
+                text.push(c);
+            }
+        }
+    }
+    push_line(&mut lines, &mut text, &mut start, indent);
+
+    (lines, version)
+}
+
+fn push_line(lines: &mut Vec<Line>, text: &mut String, start: &mut Option<Span>, indent: usize) {
+    let line = std::mem::take(text);
+    let Some(span) = start.take() else {
+        return;
+    };
+    let line = line.trim().to_string();
+    // docstrings are statements of their own
+    if line.is_empty() || line.starts_with('"') || line.starts_with('\'') {
+        return;
+    }
+    lines.push(Line {
+        text: line,
+        indent,
+        span,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> SourceUnit {
+        let mut parser = VyperParser::new(source);
+        let unit = parser.parse_module("Token", false);
+        assert!(
+            parser.diagnostics().is_empty(),
+            "{:?}",
+            parser.diagnostics()
+        );
+        unit
+    }
+
+    #[test]
+    fn erc20() {
+        let input = r#"# pragma version ^0.3.10
+"""
+@title Token # not a comment
+"""
+from ethereum.ercs import IERC20
+
+implements: IERC20
+
+event Transfer:
+    sender: indexed(address)
+    receiver: indexed(address)
+    value: uint256
+
+name: public(String[32])
+balanceOf: public(HashMap[address, uint256])
+allowance: public(HashMap[address, HashMap[address, uint256]])
+owner: address
+DECIMALS: constant(uint8) = 18
+
+@deploy
+def __init__(_name: String[32], _supply: uint256):
+    self.name = _name
+
+@external
+def transfer(
+    _to: address,  # recipient
+    _value: uint256,
+) -> bool:
+    """
+    @notice moves tokens
+    """
+    return True
+
+@internal
+def _burn(_value: uint256):
+    pass
+
+@external
+@view
+def pending(_owner: address, _start: uint256 = 0, _count: uint256 = 10) -> DynArray[uint256, 10]:
+    return []
+"#;
+        let unit = parse(input);
+        let contract = &unit.contracts[0];
+        assert_eq!(contract.name, "Token");
+        assert_eq!(contract.kind, ContractKind::Contract);
+
+        let getters: Vec<String> = contract.variables.iter().map(|v| v.signature()).collect();
+        assert_eq!(
+            getters,
+            ["name()", "balanceOf(address)", "allowance(address,address)"]
+        );
+        assert_eq!(contract.variables[1].span.line, 15);
+
+        let functions: Vec<String> = contract
+            .functions
+            .iter()
+            .filter(|f| f.visibility == Visibility::External)
+            .map(|f| f.signature())
+            .collect();
+        assert_eq!(
+            functions,
+            [
+                "transfer(address,uint256)",
+                "pending(address)",
+                "pending(address,uint256)",
+                "pending(address,uint256,uint256)",
+            ]
+        );
+        assert_eq!(contract.functions[0].span.line, 25);
+        assert_eq!(contract.functions[0].span.column, 5);
+
+        assert_eq!(
+            contract.events[0].signature(),
+            "Transfer(address,address,uint256)"
+        );
+        assert!(contract.events[0].parameters[0].indexed);
+
+        let constructor = contract.special_function(SpecialKind::Constructor).unwrap();
+        assert_eq!(constructor.parameters[0].type_name.canonical(), "string");
+    }
+
+    #[test]
+    fn types() {
+        let input = r#"
+struct Point:
+    x: int128
+    y: int128
+
+flag Roles:
+    ADMIN
+    MINTER
+
+interface Oracle:
+    def price(asset: address) -> uint256: view
+    def update(points: Point[2]): nonpayable
+
+@deploy
+def __init__(oracle: Oracle):
+    pass
+
+@external
+def f(a: Point, b: Roles, c: Oracle, d: Bytes[64], e: decimal, g: uint256[3][2]):
+    pass
+
+@external
+@payable
+def __default__():
+    pass
+"#;
+        let unit = parse(input);
+        assert_eq!(
+            unit.contracts[0].functions[0].signature(),
+            "f((int128,int128),uint256,address,bytes,fixed168x10,uint256[3][2])"
+        );
+
+        let constructor = unit.contracts[0]
+            .special_function(SpecialKind::Constructor)
+            .unwrap();
+        assert_eq!(constructor.parameters[0].type_name.canonical(), "address");
+
+        // a payable __default__ is a payable fallback, there is no receive in vyper
+        let fallback = unit.contracts[0]
+            .special_function(SpecialKind::Fallback)
+            .unwrap();
+        assert_eq!(fallback.mutability, Some(Mutability::Payable));
+        assert!(
+            unit.contracts[0]
+                .special_function(SpecialKind::Receive)
+                .is_none()
+        );
+        let output = crate::output::ContractOutput::from_contract(&unit.contracts[0], "t.vy");
+        assert!(output.is_some_and(|o| o.fallback && o.receive));
+
+        let oracle = &unit.contracts[1];
+        assert_eq!(oracle.kind, ContractKind::Interface);
+        assert_eq!(oracle.functions[0].signature(), "price(address)");
+        assert_eq!(oracle.functions[0].mutability, Some(Mutability::View));
+        assert_eq!(
+            oracle.functions[1].signature(),
+            "update((int128,int128)[2])"
+        );
+
+        // vyper 0.4 encodes decimals as int168
+        let unit = parse("#pragma version ~=0.4.0\n@external\ndef f(x: decimal):\n    pass\n");
+        assert_eq!(unit.contracts[0].functions[0].signature(), "f(int168)");
+    }
+
+    #[test]
+    fn interface_file() {
+        let input = "@external\n@view\ndef balanceOf(owner: address) -> uint256:\n    ...\n";
+        let mut parser = VyperParser::new(input);
+        let unit = parser.parse_module("IToken", true);
+
+        let interface = &unit.contracts[0];
+        assert_eq!(interface.kind, ContractKind::Interface);
+        assert_eq!(interface.functions[0].visibility, Visibility::External);
+        assert_eq!(interface.functions[0].signature(), "balanceOf(address)");
+    }
+
+    #[test]
+    fn errors() {
+        let input = "x: public(HashMap[address])\n@external\ndef f(x) -> bool:\n    pass\ny: public(uint256)\n";
+        let mut parser = VyperParser::new(input);
+        let unit = parser.parse_module("Token", false);
+
+        assert_eq!(parser.diagnostics().len(), 2);
+        assert_eq!(parser.diagnostics()[1].span().line, 3);
+        assert_eq!(unit.contracts[0].variables[0].name, "y");
+    }
+}
@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.20;
+
+interface IERC20 {}
+
+type Price is uint128;
+
+contract Getters {
+    // the example from the solidity documentation on getter functions
+    struct Data {
+        uint a;
+        bytes3 b;
+        mapping(uint => uint) map;
+        uint[3] c;
+        uint[] d;
+        bytes e;
+    }
+
+    struct Inner {
+        uint64 id;
+        string label;
+    }
+
+    struct Outer {
+        Inner inner;
+        address owner;
+        Inner[] history;
+    }
+
+    enum Status {
+        Open,
+        Closed
+    }
+
+    mapping(uint => mapping(bool => Data[])) public data;
+
+    uint256 public counter;
+    uint256[] public list;
+    uint256[][] public grid;
+    uint256[2][3] public matrix;
+    mapping(address => uint256[]) public balances;
+    mapping(address => mapping(uint256 => bool)) public flags;
+    mapping(uint256 => mapping(address => uint256[3][])) public deep;
+    Outer public outer;
+    mapping(bytes32 => Outer) public outers;
+    Status public status;
+    mapping(Status => IERC20) public tokens;
+    mapping(address owner => mapping(address spender => uint256 amount)) public allowances;
+    Price public price;
+    string public name;
+    bytes public blob;
+}